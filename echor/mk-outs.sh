@@ -6,4 +6,34 @@ OUTDIR="tests/expected"
 echo    "Hello there"    > $OUTDIR/hello1.txt
 echo    "Hello"  "there" > $OUTDIR/hello2.txt
 echo -n "Hello  there"   > $OUTDIR/hello1.n.txt
-echo -n "Hello"  "there" > $OUTDIR/hello2.n.txt
\ No newline at end of file
+echo -n "Hello"  "there" > $OUTDIR/hello2.n.txt
+echo -e 'back\\slash'        > $OUTDIR/escape.backslash.txt
+echo -e 'bell\a'             > $OUTDIR/escape.a.txt
+echo -e 'back\bspace'        > $OUTDIR/escape.b.txt
+echo -e 'stop\chere' 'never' > $OUTDIR/escape.c.txt
+echo -e '\e[1mbold\e[0m'     > $OUTDIR/escape.e.txt
+echo -e 'form\ffeed'         > $OUTDIR/escape.f.txt
+echo -e 'new\nline'          > $OUTDIR/escape.n.txt
+echo -e 'carriage\rreturn'   > $OUTDIR/escape.r.txt
+echo -e 'tab\there'          > $OUTDIR/escape.t.txt
+echo -e 'vertical\vtab'      > $OUTDIR/escape.v.txt
+echo -e 'octal \0101\0102'   > $OUTDIR/escape.0nnn.txt
+echo -e 'hex \x41\x4a'       > $OUTDIR/escape.xhh.txt
+echo -e 'unknown \q'         > $OUTDIR/escape.unknown.txt
+echo -E 'tab\there'          > $OUTDIR/escape.E.txt
+
+echo                         > $OUTDIR/empty.txt
+echo -x foo                  > $OUTDIR/dash.x.txt
+echo -- foo                  > $OUTDIR/dash.dash.txt
+echo "Hello  there" -n       > $OUTDIR/hello1.dash_n.txt
+echo -ne 'tab\there'         > $OUTDIR/escape.t.n.txt
+echo --help me               > $OUTDIR/dash.help.txt
+
+printf '%s=%5d|%-5s|%05.1f\n' a 42 left 3.14159 b -7 x 2 > $OUTDIR/printf.recycle.txt
+printf '%x %o %e %c %%\n' 255 8 1234.5 word                > $OUTDIR/printf.conversions.txt
+printf '%b|%s\n' 'tab\there' 'tab\there'                   > $OUTDIR/printf.b.txt
+
+printf '%s' 'a, b, c;'        > $OUTDIR/sep.end.txt
+printf '%s\0' a b c           > $OUTDIR/each_line.z.txt
+printf 'a\0b'                 > $OUTDIR/z.n.txt
+printf 'a\tb\n'               > $OUTDIR/sep.escape.txt
@@ -0,0 +1,317 @@
+use echor::{
+    EchoOptions,
+    style::{Color, When},
+};
+use std::ffi::{OsStr, OsString};
+
+/// Everything the command line says besides the text.
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    /// How the text is rendered
+    pub echo: EchoOptions,
+    /// `--expand`: substitute `${VAR}` and friends in the text
+    pub expand: bool,
+    /// `--strict`: an unset `${VAR}` is an error
+    pub strict: bool,
+    /// `--template FILE`: the text is the contents of FILE, `-` for stdin
+    pub template: Option<OsString>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Help,
+    Version,
+    Echo(Options, Vec<OsString>),
+    /// `--printf FORMAT ARGS...`; the format is missing if the vector is empty
+    Printf(Vec<OsString>),
+    /// An option is missing its value or has a bad one
+    Error(String),
+}
+
+/// Splits the arguments (without the program name) the way echo does.
+/// Only leading arguments that are options are taken as such: `-` followed
+/// by any of `n`, `e`, `E` and `z`, or one of the long options. The first
+/// other argument and everything after it is text, so `-x` and `--` are
+/// printed as they are. Arguments need not be valid UTF-8.
+pub fn parse(args: Vec<OsString>) -> Command {
+    if args.first().is_some_and(|arg| arg == "--printf") {
+        return Command::Printf(args.into_iter().skip(1).collect());
+    }
+    if let [arg] = args.as_slice() {
+        match arg.as_encoded_bytes() {
+            b"--help" => return Command::Help,
+            b"--version" => return Command::Version,
+            _ => {}
+        }
+    }
+    let mut options = Options::default();
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        i += 1;
+        let arg = arg.as_encoded_bytes();
+        match arg {
+            b"--each-line" => options.echo.each_line = true,
+            b"--bold" => options.echo.style.bold = true,
+            b"--underline" => options.echo.style.underline = true,
+            b"--expand" => options.expand = true,
+            b"--strict" => options.strict = true,
+            _ => {
+                if let Some(name) = VALUE_OPTIONS.into_iter().find(|name| {
+                    arg.strip_prefix(name.as_bytes())
+                        .is_some_and(|rest| rest.is_empty() || rest[0] == b'=')
+                }) {
+                    let value = match arg[name.len()..].strip_prefix(b"=") {
+                        Some(value) => echor::os_string_from_bytes(value.to_vec()),
+                        None => {
+                            let Some(value) = args.get(i) else {
+                                return Command::Error(format!(
+                                    "option '{}' requires an argument",
+                                    name
+                                ));
+                            };
+                            i += 1;
+                            value.clone()
+                        }
+                    };
+                    if let Err(message) = set_value(&mut options, name, &value) {
+                        return Command::Error(message);
+                    }
+                    continue;
+                }
+                let Some(letters) = arg.strip_prefix(b"-") else {
+                    i -= 1;
+                    break;
+                };
+                if letters.is_empty()
+                    || !letters.iter().all(|c| matches!(c, b'n' | b'e' | b'E' | b'z'))
+                {
+                    i -= 1;
+                    break;
+                }
+                for letter in letters {
+                    match letter {
+                        b'n' => options.echo.omit_newline = true,
+                        b'e' => options.echo.escapes = true,
+                        b'E' => options.echo.escapes = false,
+                        _ => options.echo.terminator = "\0".into(),
+                    }
+                }
+            }
+        }
+    }
+    Command::Echo(options, args.into_iter().skip(i).collect())
+}
+
+// The long options that take a value, as `--name VALUE` or `--name=VALUE`
+const VALUE_OPTIONS: [&str; 5] = ["--sep", "--end", "--color", "--bg", "--template"];
+
+fn set_value(options: &mut Options, name: &str, value: &OsStr) -> Result<(), String> {
+    let color = |value: &OsStr| {
+        value
+            .to_str()
+            .and_then(Color::parse)
+            .ok_or_else(|| format!("invalid color '{}'", value.to_string_lossy()))
+    };
+    match name {
+        "--sep" => options.echo.separator = value.into(),
+        "--end" => options.echo.terminator = value.into(),
+        "--template" => options.template = Some(value.into()),
+        "--color" => match value.as_encoded_bytes() {
+            b"always" => options.echo.color = When::Always,
+            b"never" => options.echo.color = When::Never,
+            b"auto" => options.echo.color = When::Auto,
+            _ => options.echo.style.foreground = Some(color(value)?),
+        },
+        _ => options.echo.style.background = Some(color(value)?),
+    }
+    Ok(())
+}
+
+pub const HELP: &str = "\
+Usage: echor [SHORT-OPTION]... [STRING]...
+  or:  echor [SHORT-OPTION]... --template FILE
+  or:  echor --printf FORMAT [ARGUMENT]...
+  or:  echor LONG-OPTION
+Echo the STRING(s) to standard output.
+
+  -n             do not output the trailing newline
+  -e             enable interpretation of backslash escapes
+  -E             disable interpretation of backslash escapes (default)
+  -z             end the output with NUL instead of newline
+      --sep STRING
+                 separate the STRING(s) with STRING instead of a space
+      --end STRING
+                 end the output with STRING instead of newline
+      --each-line
+                 end every STRING with the terminator, not just the last
+      --color COLOR
+                 print in COLOR: a name such as red or bright-red, a
+                 number from 0 to 255 or #rrggbb; or always, never or
+                 auto to say when to use colors and styles (default auto)
+      --bg COLOR
+                 print on a background of COLOR
+      --bold     print in bold
+      --underline
+                 print underlined
+      --expand   replace ${VAR}, ${VAR:-DEFAULT} and ${VAR:?MESSAGE} in the
+                 STRING(s) with values from the environment
+      --template FILE
+                 print the contents of FILE (- for standard input) with
+                 variables expanded, and no terminator of its own
+      --strict   fail on a variable that is not set instead of printing
+                 nothing for it
+      --printf FORMAT [ARGUMENT]...
+                 print ARGUMENT(s) according to FORMAT, as printf(1) does
+      --help     display this help and exit
+      --version  output version information and exit
+
+If -e is in effect, the following sequences are recognized:
+
+  \\\\      backslash
+  \\a      alert (BEL)
+  \\b      backspace
+  \\c      produce no further output
+  \\e      escape
+  \\f      form feed
+  \\n      new line
+  \\r      carriage return
+  \\t      horizontal tab
+  \\v      vertical tab
+  \\0NNN   byte with octal value NNN (1 to 3 digits)
+  \\xHH    byte with hexadecimal value HH (1 to 2 digits)
+
+With --color=auto, colors and styles are only used when standard output is
+a terminal; setting NO_COLOR turns them off and FORCE_COLOR turns them on.
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Command {
+        super::parse(args.iter().map(OsString::from).collect())
+    }
+
+    fn echo(omit_newline: bool, escapes: bool, text: &[&str]) -> Command {
+        Command::Echo(
+            Options {
+                echo: EchoOptions {
+                    omit_newline,
+                    escapes,
+                    ..EchoOptions::default()
+                },
+                ..Options::default()
+            },
+            text.iter().map(OsString::from).collect(),
+        )
+    }
+
+    #[test]
+    fn test_options() {
+        assert_eq!(parse(&["-n", "-e", "a"]), echo(true, true, &["a"]));
+        assert_eq!(parse(&["-neE", "a"]), echo(true, false, &["a"]));
+        assert_eq!(parse(&["-Ee", "-n"]), echo(true, true, &[]));
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(parse(&["-x", "-n"]), echo(false, false, &["-x", "-n"]));
+        assert_eq!(parse(&["--", "a"]), echo(false, false, &["--", "a"]));
+        assert_eq!(parse(&["-", "-n"]), echo(false, false, &["-", "-n"]));
+        assert_eq!(parse(&["-n", "a", "-e"]), echo(true, false, &["a", "-e"]));
+        assert_eq!(parse(&[]), echo(false, false, &[]));
+    }
+
+    #[test]
+    fn test_separator_and_terminator() {
+        let Command::Echo(options, text) = parse(&["--sep", ",", "--end=;", "-nz", "--sep=:", "a"])
+        else {
+            panic!("not echo");
+        };
+        assert_eq!(options.echo.separator, ":");
+        assert_eq!(options.echo.terminator, "\0");
+        assert!(options.echo.omit_newline);
+        assert!(!options.echo.each_line);
+        assert_eq!(text, ["a"]);
+
+        let Command::Echo(options, text) = parse(&["-z", "--each-line", "--end", "x", "--sepx"])
+        else {
+            panic!("not echo");
+        };
+        assert_eq!(options.echo.terminator, "x");
+        assert!(options.echo.each_line);
+        assert_eq!(text, ["--sepx"]);
+    }
+
+    #[test]
+    fn test_missing_value() {
+        assert_eq!(
+            parse(&["-n", "--end"]),
+            Command::Error("option '--end' requires an argument".to_string())
+        );
+        assert_eq!(parse(&["a", "--end"]), echo(false, false, &["a", "--end"]));
+    }
+
+    #[test]
+    fn test_style() {
+        let Command::Echo(options, text) =
+            parse(&["--color", "red", "--bg=#000000", "--bold", "--color=never", "a"])
+        else {
+            panic!("not echo");
+        };
+        assert_eq!(options.echo.style.foreground, Some(Color::Basic(1)));
+        assert_eq!(options.echo.style.background, Some(Color::Rgb(0, 0, 0)));
+        assert!(options.echo.style.bold);
+        assert!(!options.echo.style.underline);
+        assert_eq!(options.echo.color, When::Never);
+        assert_eq!(text, ["a"]);
+        assert_eq!(
+            parse(&["--bg", "pink"]),
+            Command::Error("invalid color 'pink'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_expand() {
+        let Command::Echo(options, text) = parse(&["--expand", "--strict", "--template=-"])
+        else {
+            panic!("not echo");
+        };
+        assert!(options.expand && options.strict);
+        assert_eq!(options.template.as_deref(), Some(OsStr::new("-")));
+        assert!(text.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8() {
+        let arg = |bytes: &[u8]| echor::os_string_from_bytes(bytes.to_vec());
+        let Command::Echo(options, text) =
+            super::parse(vec![arg(b"--end=\xff"), arg(b"-\xe9"), arg(b"-n")])
+        else {
+            panic!("not echo");
+        };
+        assert_eq!(options.echo.terminator, arg(b"\xff"));
+        assert_eq!(text, [arg(b"-\xe9"), arg(b"-n")]);
+        assert_eq!(
+            super::parse(vec![arg(b"--color"), arg(b"r\xe9d")]),
+            Command::Error("invalid color 'r\u{FFFD}d'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_help_and_version() {
+        assert_eq!(parse(&["--help"]), Command::Help);
+        assert_eq!(parse(&["--version"]), Command::Version);
+        assert_eq!(parse(&["--help", "a"]), echo(false, false, &["--help", "a"]));
+        assert_eq!(parse(&["-n", "--version"]), echo(true, false, &["--version"]));
+    }
+
+    #[test]
+    fn test_printf() {
+        let printf = |args: &[&str]| Command::Printf(args.iter().map(OsString::from).collect());
+        assert_eq!(parse(&["--printf", "%s\\n", "a"]), printf(&["%s\\n", "a"]));
+        assert_eq!(parse(&["--printf"]), printf(&[]));
+        assert_eq!(parse(&["-n", "--printf"]), echo(true, false, &["--printf"]));
+    }
+}
@@ -1,4 +1,10 @@
 use clap::{App, Arg};
+use echor::EchoOptions;
+use std::ffi::OsString;
+
+// Shared with the echor binary, which exits the same way on write errors
+#[path = "../output.rs"]
+mod output;
 
 fn main() {
     let matches = App::new("echor")
@@ -20,7 +26,14 @@ fn main() {
         )
         .get_matches();
 
-    let text = matches.values_of_lossy("text").unwrap();
-    let omit_newline = matches.is_present("omit_newline");
-    print!("{}{}", text.join(" "), if omit_newline { "" } else { "\n" });
-}
\ No newline at end of file
+    let text: Vec<OsString> = matches
+        .values_of_os("text")
+        .unwrap()
+        .map(OsString::from)
+        .collect();
+    let options = EchoOptions {
+        omit_newline: matches.is_present("omit_newline"),
+        ..EchoOptions::default()
+    };
+    output::write_or_exit(&echor::render(&options, &text));
+}
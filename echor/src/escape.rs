@@ -0,0 +1,147 @@
+/// Which octal escapes are recognized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Octal {
+    /// `\0NNN`, as in `echo -e`
+    Zero,
+    /// `\NNN`, as in a printf format
+    Plain,
+    /// Either, as in a printf `%b` argument
+    Both,
+}
+
+/// Appends `bytes` to `out` with the backslash escapes of `echo -e`
+/// interpreted. Returns false if `\c` was found, in which case nothing
+/// more may be printed, not even the trailing newline.
+pub fn unescape(bytes: &[u8], out: &mut Vec<u8>) -> bool {
+    unescape_with(bytes, Octal::Zero, out)
+}
+
+/// Like `unescape`, with the given kind of octal escapes.
+pub fn unescape_with(bytes: &[u8], octal: Octal, out: &mut Vec<u8>) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        i += 1;
+        if byte != b'\\' {
+            out.push(byte);
+            continue;
+        }
+        match escape(&bytes[i..], octal, out) {
+            Some(len) => i += len,
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Interprets the escape sequence that follows a backslash, appending its
+/// value to `out`. Returns how many bytes after the backslash it took, or
+/// None for `\c`.
+pub fn escape(bytes: &[u8], octal: Octal, out: &mut Vec<u8>) -> Option<usize> {
+    // A trailing backslash has nothing to escape and is printed as is
+    let Some(&escape) = bytes.first() else {
+        out.push(b'\\');
+        return Some(0);
+    };
+    let value = match escape {
+        b'\\' => b'\\',
+        b'"' if octal != Octal::Zero => b'"',
+        b'a' => 0x07,
+        b'b' => 0x08,
+        b'c' => return None,
+        b'e' => 0x1b,
+        b'f' => 0x0c,
+        b'n' => b'\n',
+        b'r' => b'\r',
+        b't' => b'\t',
+        b'v' => 0x0b,
+        b'0' if octal != Octal::Plain => {
+            // Up to three octal digits; like GNU echo, values past
+            // \0377 wrap around
+            let (value, len) = parse_digits(&bytes[1..], 8, 3);
+            out.push(value as u8);
+            return Some(1 + len);
+        }
+        b'0'..=b'7' if octal != Octal::Zero => {
+            let (value, len) = parse_digits(bytes, 8, 3);
+            out.push(value as u8);
+            return Some(len);
+        }
+        b'x' => match parse_digits(&bytes[1..], 16, 2) {
+            (_, 0) => {
+                out.extend_from_slice(b"\\x");
+                return Some(1);
+            }
+            (value, len) => {
+                out.push(value as u8);
+                return Some(1 + len);
+            }
+        },
+        _ => {
+            out.extend_from_slice(&[b'\\', escape]);
+            return Some(1);
+        }
+    };
+    out.push(value);
+    Some(1)
+}
+
+// The value of up to `max` leading digits and how many there were
+fn parse_digits(bytes: &[u8], radix: u32, max: usize) -> (u32, usize) {
+    let mut value = 0;
+    let mut len = 0;
+    for digit in bytes.iter().take(max).map_while(|&b| (b as char).to_digit(radix)) {
+        value = value * radix + digit;
+        len += 1;
+    }
+    (value, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unescaped(text: &str) -> (Vec<u8>, bool) {
+        let mut out = vec![];
+        let more = unescape(text.as_bytes(), &mut out);
+        (out, more)
+    }
+
+    #[test]
+    fn test_simple_escapes() {
+        assert_eq!(
+            unescaped(r"\\\a\b\e\f\n\r\t\v"),
+            (b"\\\x07\x08\x1b\x0c\n\r\t\x0b".to_vec(), true)
+        );
+    }
+
+    #[test]
+    fn test_numeric_escapes() {
+        assert_eq!(unescaped(r"\0101\0\x41\x4a2"), (b"A\0AJ2".to_vec(), true));
+        assert_eq!(unescaped(r"\01234"), (b"S4".to_vec(), true));
+        assert_eq!(unescaped(r"\0777"), (vec![0xff], true));
+        assert_eq!(unescaped(r"\xg"), (b"\\xg".to_vec(), true));
+    }
+
+    #[test]
+    fn test_stop() {
+        assert_eq!(unescaped(r"ab\cde"), (b"ab".to_vec(), false));
+    }
+
+    #[test]
+    fn test_octal_styles() {
+        let unescaped = |text: &str, octal| {
+            let mut out = vec![];
+            unescape_with(text.as_bytes(), octal, &mut out);
+            out
+        };
+        assert_eq!(unescaped(r"\101\0101", Octal::Zero), b"\\101A");
+        assert_eq!(unescaped(r"\101\0101", Octal::Plain), b"A\x081");
+        assert_eq!(unescaped(r"\101\0101", Octal::Both), b"AA");
+    }
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(unescaped(r"\q\1 end\"), (b"\\q\\1 end\\".to_vec(), true));
+    }
+}
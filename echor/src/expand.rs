@@ -0,0 +1,174 @@
+/// Expands `${VAR}`, `${VAR:-default}` and `${VAR:?message}` in `text`,
+/// looking the variables up with `lookup`. An unset `${VAR}` becomes empty
+/// unless `strict` is set, in which case it is an error like `${VAR:?}`.
+/// Anything else, such as `$VAR` or `${1abc}`, is left as it is. Only the
+/// names need to be ASCII; the text and the values can be any bytes.
+pub fn expand(
+    text: &[u8],
+    strict: bool,
+    lookup: &dyn Fn(&str) -> Option<Vec<u8>>,
+) -> Result<Vec<u8>, String> {
+    let mut out = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.windows(2).position(|pair| pair == b"${") {
+        out.extend_from_slice(&rest[..start]);
+        let inner = &rest[start + 2..];
+        let value = match closing_brace(inner) {
+            Some(end) => substitute(&inner[..end], strict, lookup)?.map(|value| (value, end)),
+            None => None,
+        };
+        match value {
+            Some((value, end)) => {
+                out.extend_from_slice(&value);
+                rest = &inner[end + 1..];
+            }
+            None => {
+                out.extend_from_slice(b"${");
+                rest = inner;
+            }
+        }
+    }
+    out.extend_from_slice(rest);
+    Ok(out)
+}
+
+// The index of the `}` that closes a `${`, skipping nested braces
+fn closing_brace(text: &[u8]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &byte) in text.iter().enumerate() {
+        match byte {
+            b'{' => depth += 1,
+            b'}' if depth == 0 => return Some(i),
+            b'}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+// The value of the expression between `${` and `}`, or None if it isn't one
+fn substitute(
+    expr: &[u8],
+    strict: bool,
+    lookup: &dyn Fn(&str) -> Option<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, String> {
+    let len = expr
+        .iter()
+        .position(|&b| !(b.is_ascii_alphanumeric() || b == b'_'))
+        .unwrap_or(expr.len());
+    let (name, operator) = expr.split_at(len);
+    if name.is_empty() || name[0].is_ascii_digit() {
+        return Ok(None);
+    }
+    let name = std::str::from_utf8(name).expect("ASCII name");
+    let value = lookup(name);
+    let set = value.as_ref().is_some_and(|value| !value.is_empty());
+    if operator.is_empty() {
+        return match value {
+            Some(value) => Ok(Some(value)),
+            None if strict => Err(format!("{}: unbound variable", name)),
+            None => Ok(Some(vec![])),
+        };
+    }
+    if let Some(default) = operator.strip_prefix(b":-") {
+        return match value {
+            Some(value) if set => Ok(Some(value)),
+            _ => expand(default, strict, lookup).map(Some),
+        };
+    }
+    if let Some(message) = operator.strip_prefix(b":?") {
+        return match value {
+            Some(value) if set => Ok(Some(value)),
+            _ if message.is_empty() => Err(format!("{}: parameter null or not set", name)),
+            _ => {
+                let message = expand(message, strict, lookup)?;
+                Err(format!("{}: {}", name, String::from_utf8_lossy(&message)))
+            }
+        };
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<Vec<u8>> {
+        match name {
+            "USER" => Some(b"ferris".to_vec()),
+            "EMPTY" => Some(vec![]),
+            "HOME_DIR" => Some(b"/home/ferris".to_vec()),
+            "LATIN1" => Some(b"caf\xe9".to_vec()),
+            _ => None,
+        }
+    }
+
+    fn run(text: &str, strict: bool) -> Result<String, String> {
+        expand(text.as_bytes(), strict, &lookup).map(|out| String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_variables() {
+        assert_eq!(
+            run("hi ${USER} at ${HOME_DIR}!", false).unwrap(),
+            "hi ferris at /home/ferris!"
+        );
+        assert_eq!(run("[${NOPE}][${EMPTY}]", false).unwrap(), "[][]");
+        assert_eq!(run("${USER}${USER}", true).unwrap(), "ferrisferris");
+    }
+
+    #[test]
+    fn test_default() {
+        assert_eq!(run("${NOPE:-guest}", false).unwrap(), "guest");
+        assert_eq!(run("${EMPTY:-guest}", false).unwrap(), "guest");
+        assert_eq!(run("${USER:-guest}", false).unwrap(), "ferris");
+        assert_eq!(run("${NOPE:-${USER}-x}", false).unwrap(), "ferris-x");
+        assert_eq!(run("${NOPE:-}", true).unwrap(), "");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            run("${NOPE:?is required}", false),
+            Err("NOPE: is required".to_string())
+        );
+        assert_eq!(
+            run("${EMPTY:?}", false),
+            Err("EMPTY: parameter null or not set".to_string())
+        );
+        assert_eq!(run("${USER:?x}", false).unwrap(), "ferris");
+        assert_eq!(
+            run("a ${NOPE}", true),
+            Err("NOPE: unbound variable".to_string())
+        );
+        assert_eq!(
+            run("${NOPE:-${OTHER}}", true),
+            Err("OTHER: unbound variable".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(
+            expand(b"\xff ${LATIN1} ${NOPE:-\xfe}", false, &lookup).unwrap(),
+            b"\xff caf\xe9 \xfe"
+        );
+    }
+
+    #[test]
+    fn test_left_alone() {
+        for text in [
+            "$USER",
+            "${",
+            "${USER",
+            "${1abc}",
+            "${}",
+            "${USER:+x}",
+            "${A B}",
+            "$${USER",
+        ] {
+            assert_eq!(run(text, true).unwrap(), text, "{}", text);
+        }
+        assert_eq!(run("${ ${USER}", false).unwrap(), "${ ferris");
+    }
+}
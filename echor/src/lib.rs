@@ -0,0 +1,194 @@
+use std::{
+    ffi::OsString,
+    io::{self, Write},
+};
+
+use style::{Style, When};
+
+pub mod escape;
+pub mod expand;
+pub mod printf;
+pub mod style;
+
+/// How `render` prints the text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EchoOptions {
+    /// `-n`: leave out the trailing newline
+    pub omit_newline: bool,
+    /// `-e`/`-E`: interpret backslash escapes
+    pub escapes: bool,
+    /// `--sep`: printed between the arguments
+    pub separator: OsString,
+    /// `--end`/`-z`: printed after the last argument
+    pub terminator: OsString,
+    /// `--each-line`: end every argument with the terminator
+    pub each_line: bool,
+    /// `--color`, `--bg`, `--bold` and `--underline`
+    pub style: Style,
+    /// `--color always|never|auto`
+    pub color: When,
+}
+
+impl Default for EchoOptions {
+    fn default() -> Self {
+        EchoOptions {
+            omit_newline: false,
+            escapes: false,
+            separator: " ".into(),
+            terminator: "\n".into(),
+            each_line: false,
+            style: Style::default(),
+            color: When::Auto,
+        }
+    }
+}
+
+/// Turns bytes, such as those of `OsStr::as_encoded_bytes`, back into an
+/// `OsString`. On Unix any bytes will do; elsewhere bytes that aren't
+/// UTF-8 are replaced.
+pub fn os_string_from_bytes(bytes: Vec<u8>) -> OsString {
+    #[cfg(unix)]
+    {
+        std::os::unix::ffi::OsStringExt::from_vec(bytes)
+    }
+    #[cfg(not(unix))]
+    {
+        String::from_utf8_lossy(&bytes).into_owned().into()
+    }
+}
+
+/// Writes all of `out` to stdout and flushes it.
+pub fn write_stdout(out: &[u8]) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(out)?;
+    stdout.flush()
+}
+
+/// The bytes echo prints for `text`: the arguments joined by the
+/// separator, then the terminator. The arguments are taken byte for byte,
+/// whether they are UTF-8 or not.
+pub fn render(options: &EchoOptions, text: &[OsString]) -> Vec<u8> {
+    let mut out = vec![];
+    // The style covers the text but not the terminator, so that a colored
+    // line doesn't bleed into the next one
+    let start = options.style.start().filter(|_| style::enabled(options.color));
+    if let Some(start) = &start {
+        out.extend_from_slice(start.as_bytes());
+    }
+    let more = render_text(options, text, &mut out);
+    if start.is_some() {
+        out.extend_from_slice(style::RESET.as_bytes());
+    }
+    if more && !options.omit_newline {
+        push(options, options.terminator.as_encoded_bytes(), &mut out);
+    }
+    out
+}
+
+// The arguments and the separators between them; false if \c ended the
+// output
+fn render_text(options: &EchoOptions, text: &[OsString], out: &mut Vec<u8>) -> bool {
+    let separator = if options.each_line {
+        &options.terminator
+    } else {
+        &options.separator
+    };
+    for (i, text) in text.iter().enumerate() {
+        if i > 0 && !push(options, separator.as_encoded_bytes(), out) {
+            return false;
+        }
+        if !push(options, text.as_encoded_bytes(), out) {
+            return false;
+        }
+    }
+    true
+}
+
+// Appends one piece of the output, interpreting escapes under -e. Returns
+// false once \c has ended the output.
+fn push(options: &EchoOptions, text: &[u8], out: &mut Vec<u8>) -> bool {
+    if options.escapes {
+        escape::unescape(text, out)
+    } else {
+        out.extend_from_slice(text);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::Color;
+
+    fn render(options: &EchoOptions, text: &[&str]) -> Vec<u8> {
+        let text: Vec<OsString> = text.iter().map(OsString::from).collect();
+        super::render(options, &text)
+    }
+
+    #[test]
+    fn test_plain() {
+        let options = EchoOptions::default();
+        assert_eq!(render(&options, &["Hello", "there"]), b"Hello there\n");
+        assert_eq!(render(&options, &[]), b"\n");
+        assert_eq!(render(&options, &[r"a\tb"]), b"a\\tb\n");
+        let options = EchoOptions {
+            omit_newline: true,
+            ..EchoOptions::default()
+        };
+        assert_eq!(render(&options, &["a", "b"]), b"a b");
+    }
+
+    #[test]
+    fn test_escapes() {
+        let options = EchoOptions {
+            escapes: true,
+            separator: r"\t".into(),
+            ..EchoOptions::default()
+        };
+        assert_eq!(render(&options, &[r"a\x41", r"\0102"]), b"aA\tB\n");
+        assert_eq!(render(&options, &["a", r"b\cc", "d"]), b"a\tb");
+    }
+
+    #[test]
+    fn test_separator_and_terminator() {
+        let options = EchoOptions {
+            separator: ", ".into(),
+            terminator: ";".into(),
+            ..EchoOptions::default()
+        };
+        assert_eq!(render(&options, &["a", "b", "c"]), b"a, b, c;");
+        let options = EchoOptions {
+            terminator: "\0".into(),
+            each_line: true,
+            ..EchoOptions::default()
+        };
+        assert_eq!(render(&options, &["a", "b"]), b"a\0b\0");
+    }
+
+    #[test]
+    fn test_style() {
+        let style = Style {
+            foreground: Color::parse("green"),
+            ..Style::default()
+        };
+        let options = EchoOptions {
+            style: style.clone(),
+            color: When::Always,
+            ..EchoOptions::default()
+        };
+        assert_eq!(render(&options, &["ok"]), b"\x1b[32mok\x1b[0m\n");
+        let options = EchoOptions {
+            style,
+            color: When::Never,
+            ..EchoOptions::default()
+        };
+        assert_eq!(render(&options, &["ok"]), b"ok\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8() {
+        let text = [os_string_from_bytes(b"caf\xe9".to_vec()), OsString::from("!")];
+        assert_eq!(super::render(&EchoOptions::default(), &text), b"caf\xe9 !\n");
+    }
+}
@@ -1,22 +1,81 @@
-use clap::Parser;
+use args::Command;
+use echor::{expand, printf};
+use output::write_or_exit;
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    fs,
+    io::{self, Read},
+    process,
+};
 
-#[derive(Parser, Debug)]
-#[command(author, version, about)]
-/// Rust version of echo
-struct Args {
-    /// Input text
-    #[arg(required(true))]
-    text: Vec<String>,
-    /// Do not print newline
-    #[arg(short('n'))]
-    omit_newline: bool,
-}
+mod args;
+mod output;
 
 fn main() {
-    let args = Args::parse();
-    print!(
-        "{}{}",
-        args.text.join(" "),
-        if args.omit_newline { "" } else { "\n" }
-    );
-}
\ No newline at end of file
+    let (mut options, mut text) = match args::parse(env::args_os().skip(1).collect()) {
+        Command::Help => {
+            write_or_exit(args::HELP.as_bytes());
+            return;
+        }
+        Command::Version => {
+            write_or_exit(format!("echor {}\n", env!("CARGO_PKG_VERSION")).as_bytes());
+            return;
+        }
+        Command::Echo(options, text) => (options, text),
+        Command::Printf(args) => {
+            let Some((format, args)) = args.split_first() else {
+                eprintln!("echor: missing operand");
+                process::exit(1);
+            };
+            let mut out = vec![];
+            let ok = printf::printf(format.as_encoded_bytes(), args, &mut out);
+            write_or_exit(&out);
+            if !ok {
+                process::exit(1);
+            }
+            return;
+        }
+        Command::Error(message) => {
+            eprintln!("echor: {}", message);
+            process::exit(1);
+        }
+    };
+    if let Some(filename) = &options.template {
+        if let Some(extra) = text.first() {
+            eprintln!("echor: extra operand '{}'", extra.to_string_lossy());
+            process::exit(1);
+        }
+        match read_template(filename) {
+            Ok(contents) => text.push(echor::os_string_from_bytes(contents)),
+            Err(e) => {
+                eprintln!("echor: {}: {}", filename.to_string_lossy(), e);
+                process::exit(1);
+            }
+        }
+        options.echo.omit_newline = true;
+    }
+    if options.expand || options.template.is_some() {
+        let lookup = |name: &str| env::var_os(name).map(OsString::into_encoded_bytes);
+        for text in text.iter_mut() {
+            match expand::expand(text.as_encoded_bytes(), options.strict, &lookup) {
+                Ok(expanded) => *text = echor::os_string_from_bytes(expanded),
+                Err(message) => {
+                    eprintln!("echor: {}", message);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+    write_or_exit(&echor::render(&options.echo, &text));
+}
+
+fn read_template(filename: &OsStr) -> io::Result<Vec<u8>> {
+    if filename == "-" {
+        let mut contents = vec![];
+        io::stdin().read_to_end(&mut contents)?;
+        Ok(contents)
+    } else {
+        fs::read(filename)
+    }
+}
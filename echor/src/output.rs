@@ -0,0 +1,16 @@
+use std::{io, process};
+
+/// Writes all of `out` to stdout, or ends the program if that fails. A
+/// closed pipe ends it quietly, with the status that being killed by
+/// SIGPIPE gives in a shell; any other error, such as a full disk, is
+/// reported.
+pub fn write_or_exit(out: &[u8]) {
+    match echor::write_stdout(out) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => process::exit(128 + 13),
+        Err(e) => {
+            eprintln!("echor: write error: {}", e);
+            process::exit(1);
+        }
+    }
+}
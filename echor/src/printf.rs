@@ -0,0 +1,678 @@
+use crate::escape::{self, Octal};
+use std::ffi::OsString;
+
+/// Formats `args` the way printf(1) does and appends the result to `out`.
+/// The format is used again for as long as it consumes arguments and some
+/// are left. Bad numbers are reported on stderr and count as zero, or as
+/// far as they could be read; returns false if anything was reported.
+pub fn printf(format: &[u8], args: &[OsString], out: &mut Vec<u8>) -> bool {
+    let mut printer = Printer { args, next: 0, ok: true };
+    loop {
+        let start = printer.next;
+        match printer.print(format, out) {
+            Flow::Continue => {}
+            Flow::Stop => return printer.ok,
+            Flow::Fail => return false,
+        }
+        if printer.next == start || printer.next >= args.len() {
+            break;
+        }
+    }
+    if let Some(extra) = args.get(printer.next) {
+        eprintln!(
+            "echor: warning: ignoring excess arguments, starting with '{}'",
+            extra.to_string_lossy()
+        );
+    }
+    printer.ok
+}
+
+enum Flow {
+    Continue,
+    /// `\c` ends all output
+    Stop,
+    /// An invalid conversion ends the run with an error
+    Fail,
+}
+
+// The largest width or precision C's printf takes
+const MAX_FIELD: usize = i32::MAX as usize;
+
+// Past this many digits every f64 has been written out exactly, so the rest
+// are zeros. format! can't be asked for more than u16::MAX anyway.
+const EXACT_DIGITS: usize = 1100;
+
+#[derive(Debug, Default, PartialEq)]
+struct Spec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alt: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+struct Printer<'a> {
+    args: &'a [OsString],
+    next: usize,
+    ok: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn print(&mut self, format: &[u8], out: &mut Vec<u8>) -> Flow {
+        let mut i = 0;
+        while i < format.len() {
+            let byte = format[i];
+            i += 1;
+            match byte {
+                b'\\' => match escape::escape(&format[i..], Octal::Plain, out) {
+                    Some(len) => i += len,
+                    None => return Flow::Stop,
+                },
+                b'%' if format.get(i) == Some(&b'%') => {
+                    out.push(b'%');
+                    i += 1;
+                }
+                b'%' => {
+                    let start = i - 1;
+                    let (spec, conversion, len) = match self.parse_spec(&format[i..]) {
+                        Ok(parsed) => parsed,
+                        Err(message) => {
+                            eprintln!("echor: {}", message);
+                            return Flow::Fail;
+                        }
+                    };
+                    i += len;
+                    let Some(conversion) = conversion else {
+                        eprintln!(
+                            "echor: {}: invalid conversion specification",
+                            String::from_utf8_lossy(&format[start..i])
+                        );
+                        return Flow::Fail;
+                    };
+                    if !self.convert(&spec, conversion, out) {
+                        return Flow::Stop;
+                    }
+                }
+                _ => out.push(byte),
+            }
+        }
+        Flow::Continue
+    }
+
+    // Reads the flags, width, precision and length modifiers after a `%`.
+    // Returns the conversion character, if it is a valid one, and how many
+    // bytes were read including it, or the message for a width or precision
+    // that is out of range.
+    fn parse_spec(&mut self, format: &[u8]) -> Result<(Spec, Option<u8>, usize), String> {
+        let mut spec = Spec::default();
+        // %b takes no flags, width or precision at all
+        if format.first() == Some(&b'b') {
+            return Ok((spec, Some(b'b'), 1));
+        }
+        // As in GNU printf, some flags are invalid with some conversions
+        let mut allowed = b"diouxXfFeEgGcs".to_vec();
+        let mut disallow = |conversions: &[u8]| allowed.retain(|c| !conversions.contains(c));
+        let mut i = 0;
+        while let Some(&flag) = format.get(i) {
+            match flag {
+                b'-' => spec.left = true,
+                b'+' => spec.plus = true,
+                b' ' => spec.space = true,
+                b'#' => {
+                    spec.alt = true;
+                    disallow(b"cdisu");
+                }
+                b'0' => {
+                    spec.zero = true;
+                    disallow(b"cs");
+                }
+                // Thousands grouping does nothing in the C locale
+                b'\'' => disallow(b"ceEosxX"),
+                _ => break,
+            }
+            i += 1;
+        }
+        let invalid = |what, text: &[u8]| {
+            format!("invalid {}: '{}'", what, String::from_utf8_lossy(text))
+        };
+        if format.get(i) == Some(&b'*') {
+            let arg = self.peek_arg();
+            let width = self.next_integer(true);
+            if width.unsigned_abs() > MAX_FIELD as u128 {
+                return Err(invalid("field width", arg));
+            }
+            spec.left |= width < 0;
+            spec.width = width.unsigned_abs() as usize;
+            i += 1;
+        } else {
+            let digits = &format[i..i + count_digits(&format[i..])];
+            spec.width = parse_count(digits);
+            if spec.width > MAX_FIELD {
+                return Err(invalid("field width", digits));
+            }
+            i += digits.len();
+        }
+        if format.get(i) == Some(&b'.') {
+            i += 1;
+            disallow(b"c");
+            if format.get(i) == Some(&b'*') {
+                // A negative precision is taken as if it were left out
+                let arg = self.peek_arg();
+                let precision = self.next_integer(true);
+                if precision > MAX_FIELD as i128 {
+                    return Err(invalid("precision", arg));
+                }
+                spec.precision = (precision >= 0).then_some(precision as usize);
+                i += 1;
+            } else {
+                let digits = &format[i..i + count_digits(&format[i..])];
+                let precision = parse_count(digits);
+                if precision > MAX_FIELD {
+                    return Err(invalid("precision", digits));
+                }
+                spec.precision = Some(precision);
+                i += digits.len();
+            }
+        }
+        while format.get(i).is_some_and(|b| b"hlLjztq".contains(b)) {
+            i += 1;
+        }
+        Ok(match format.get(i) {
+            Some(&c) if allowed.contains(&c) => (spec, Some(c), i + 1),
+            Some(_) => (spec, None, i + 1),
+            None => (spec, None, i),
+        })
+    }
+
+    // Returns false if a `%b` argument ended the output with `\c`
+    fn convert(&mut self, spec: &Spec, conversion: u8, out: &mut Vec<u8>) -> bool {
+        match conversion {
+            b'd' | b'i' => {
+                let value = self.next_integer(true);
+                let sign = sign(value < 0, spec);
+                pad_number(spec, sign, "", &value.unsigned_abs().to_string(), out);
+            }
+            b'o' | b'u' | b'x' | b'X' => {
+                let value = self.next_integer(false) as u64;
+                let (prefix, digits) = match conversion {
+                    b'o' => ("", format!("{:o}", value)),
+                    b'u' => ("", value.to_string()),
+                    b'x' if spec.alt && value != 0 => ("0x", format!("{:x}", value)),
+                    b'x' => ("", format!("{:x}", value)),
+                    _ if spec.alt && value != 0 => ("0X", format!("{:X}", value)),
+                    _ => ("", format!("{:X}", value)),
+                };
+                let mut digits = zero_extend(&digits, spec.precision);
+                if conversion == b'o' && spec.alt && !digits.starts_with('0') {
+                    digits.insert(0, '0');
+                }
+                pad(spec, prefix, digits.as_bytes(), spec.precision.is_none(), out);
+            }
+            b'f' | b'F' | b'e' | b'E' | b'g' | b'G' => {
+                let value = self.next_float();
+                let sign = sign(value.is_sign_negative() && !value.is_nan(), spec);
+                let body = format_float(value.abs(), conversion, spec);
+                pad(spec, sign, body.as_bytes(), value.is_finite(), out);
+            }
+            b'c' => {
+                // An empty or missing argument prints a NUL, as in C
+                let arg = self.next_arg().unwrap_or_default();
+                let byte = arg.first().copied().unwrap_or(0);
+                pad(spec, "", &[byte], false, out);
+            }
+            b's' => {
+                let arg = self.next_arg().unwrap_or_default();
+                pad(spec, "", truncate(arg, spec.precision), false, out);
+            }
+            _ => {
+                let arg = self.next_arg().unwrap_or_default();
+                let mut text = vec![];
+                if !escape::unescape_with(arg, Octal::Both, &mut text) {
+                    out.extend_from_slice(&text);
+                    return false;
+                }
+                pad(spec, "", truncate(&text, spec.precision), false, out);
+            }
+        }
+        true
+    }
+
+    fn peek_arg(&self) -> &'a [u8] {
+        self.args.get(self.next).map_or(b"", |arg| arg.as_encoded_bytes())
+    }
+
+    fn next_arg(&mut self) -> Option<&'a [u8]> {
+        let arg = self.args.get(self.next)?;
+        self.next += 1;
+        Some(arg.as_encoded_bytes())
+    }
+
+    // A missing argument counts as zero. Unsigned conversions get their
+    // value back as a u64, with negative numbers wrapped around as
+    // strtoumax does.
+    fn next_integer(&mut self, signed: bool) -> i128 {
+        let Some(bytes) = self.next_arg() else {
+            return 0;
+        };
+        // Anything that isn't UTF-8 can't be part of a number
+        let arg = String::from_utf8_lossy(bytes);
+        let (negative, magnitude, len) = match self.char_constant(bytes) {
+            Some(value) => (false, value as u128, arg.len()),
+            None => parse_integer(&arg),
+        };
+        let value = if signed {
+            let value = if negative { -(magnitude as i128) } else { magnitude as i128 };
+            let clamped = value.clamp(i64::MIN as i128, i64::MAX as i128);
+            if clamped != value {
+                return self.out_of_range(&arg, clamped);
+            }
+            value
+        } else if magnitude > u64::MAX as u128 {
+            return self.out_of_range(&arg, u64::MAX as i128);
+        } else if negative {
+            (magnitude as u64).wrapping_neg() as i128
+        } else {
+            magnitude as i128
+        };
+        self.check_converted(&arg, len);
+        value
+    }
+
+    fn next_float(&mut self) -> f64 {
+        let Some(bytes) = self.next_arg() else {
+            return 0.0;
+        };
+        if let Some(value) = self.char_constant(bytes) {
+            return value as f64;
+        }
+        let arg = String::from_utf8_lossy(bytes);
+        let (value, len) = parse_float(&arg);
+        self.check_converted(&arg, len);
+        value
+    }
+
+    // A leading quote makes the value that of the byte after it, as in
+    // GNU printf, which also warns about anything after that byte
+    fn char_constant(&self, bytes: &[u8]) -> Option<u8> {
+        if !matches!(bytes.first(), Some(b'\'' | b'"')) || bytes.len() < 2 {
+            return None;
+        }
+        if bytes.len() > 2 {
+            eprintln!(
+                "echor: warning: {}: character(s) following character constant have been ignored",
+                String::from_utf8_lossy(&bytes[2..])
+            );
+        }
+        Some(bytes[1])
+    }
+
+    fn out_of_range(&mut self, arg: &str, value: i128) -> i128 {
+        eprintln!("echor: '{}': Numerical result out of range", arg);
+        self.ok = false;
+        value
+    }
+
+    // The messages GNU printf gives for an argument of which only the first
+    // `len` bytes are a number
+    fn check_converted(&mut self, arg: &str, len: usize) {
+        if len == arg.len() {
+            return;
+        }
+        if len == 0 {
+            eprintln!("echor: '{}': expected a numeric value", arg);
+        } else {
+            eprintln!("echor: '{}': value not completely converted", arg);
+        }
+        self.ok = false;
+    }
+}
+
+fn count_digits(bytes: &[u8]) -> usize {
+    bytes.iter().take_while(|b| b.is_ascii_digit()).count()
+}
+
+fn parse_count(digits: &[u8]) -> usize {
+    digits
+        .iter()
+        .fold(0usize, |n, d| n.saturating_mul(10).saturating_add((d - b'0') as usize))
+}
+
+// Like strtoimax with base 0: leading blanks, a sign, then hex after 0x,
+// octal after 0 or decimal. Returns the sign, the magnitude (saturated) and
+// how many bytes make up the number, which is 0 if there is none.
+fn parse_integer(arg: &str) -> (bool, u128, usize) {
+    let bytes = arg.as_bytes();
+    let mut i = bytes.len() - arg.trim_start().len();
+    let negative = bytes.get(i) == Some(&b'-');
+    if matches!(bytes.get(i), Some(b'-' | b'+')) {
+        i += 1;
+    }
+    let mut radix = 10;
+    if bytes.get(i) == Some(&b'0') {
+        let hex = matches!(bytes.get(i + 1), Some(b'x' | b'X'))
+            && bytes.get(i + 2).is_some_and(u8::is_ascii_hexdigit);
+        radix = if hex { 16 } else { 8 };
+        if hex {
+            i += 2;
+        }
+    }
+    let start = i;
+    let mut magnitude: u128 = 0;
+    while let Some(digit) = bytes.get(i).and_then(|&b| (b as char).to_digit(radix)) {
+        magnitude = magnitude.saturating_mul(radix as u128).saturating_add(digit as u128);
+        i += 1;
+    }
+    if i == start {
+        return (false, 0, 0);
+    }
+    (negative, magnitude, i)
+}
+
+// Like strtod: the longest prefix that reads as a decimal number, infinity
+// or NaN
+fn parse_float(arg: &str) -> (f64, usize) {
+    let bytes = arg.as_bytes();
+    let start = bytes.len() - arg.trim_start().len();
+    let mut i = start;
+    if matches!(bytes.get(i), Some(b'-' | b'+')) {
+        i += 1;
+    }
+    let rest = arg[i..].to_ascii_lowercase();
+    for word in ["infinity", "inf", "nan"] {
+        if rest.starts_with(word) {
+            let end = i + word.len();
+            return (arg[start..end].parse().unwrap_or(0.0), end);
+        }
+    }
+    if let Some((value, len)) = parse_hex_float(&bytes[i..]) {
+        let value = if bytes[start] == b'-' { -value } else { value };
+        return (value, i + len);
+    }
+    let int_digits = count_digits(&bytes[i..]);
+    i += int_digits;
+    let mut frac_digits = 0;
+    if bytes.get(i) == Some(&b'.') {
+        frac_digits = count_digits(&bytes[i + 1..]);
+        if int_digits + frac_digits > 0 {
+            i += 1 + frac_digits;
+        }
+    }
+    if int_digits + frac_digits == 0 {
+        return (0.0, 0);
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let mut j = i + 1;
+        if matches!(bytes.get(j), Some(b'-' | b'+')) {
+            j += 1;
+        }
+        let exp_digits = count_digits(&bytes[j..]);
+        if exp_digits > 0 {
+            i = j + exp_digits;
+        }
+    }
+    (arg[start..i].parse().unwrap_or(0.0), i)
+}
+
+// C99 hex floats such as 0x1.8p3, which strtod reads too. A bare 0x is
+// read as just the 0.
+fn parse_hex_float(bytes: &[u8]) -> Option<(f64, usize)> {
+    if !(bytes.len() > 2 && bytes[0] == b'0' && matches!(bytes[1], b'x' | b'X')) {
+        return None;
+    }
+    let hex_digit = |i: usize| bytes.get(i).and_then(|&b| (b as char).to_digit(16));
+    let mut i = 2;
+    let mut mantissa = 0.0;
+    let mut exponent = 0;
+    let mut digits = 0;
+    while let Some(digit) = hex_digit(i) {
+        mantissa = mantissa * 16.0 + digit as f64;
+        digits += 1;
+        i += 1;
+    }
+    if bytes.get(i) == Some(&b'.') && (digits > 0 || hex_digit(i + 1).is_some()) {
+        i += 1;
+        while let Some(digit) = hex_digit(i) {
+            mantissa = mantissa * 16.0 + digit as f64;
+            exponent -= 4;
+            digits += 1;
+            i += 1;
+        }
+    }
+    if digits == 0 {
+        return Some((0.0, 1));
+    }
+    if matches!(bytes.get(i), Some(b'p' | b'P')) {
+        let mut j = i + 1;
+        let negative = bytes.get(j) == Some(&b'-');
+        if matches!(bytes.get(j), Some(b'-' | b'+')) {
+            j += 1;
+        }
+        let exp_digits = count_digits(&bytes[j..]);
+        if exp_digits > 0 {
+            let value = parse_count(&bytes[j..j + exp_digits]).min(100_000) as i32;
+            exponent += if negative { -value } else { value };
+            i = j + exp_digits;
+        }
+    }
+    Some((mantissa * 2f64.powi(exponent), i))
+}
+
+fn sign(negative: bool, spec: &Spec) -> &'static str {
+    if negative {
+        "-"
+    } else if spec.plus {
+        "+"
+    } else if spec.space {
+        " "
+    } else {
+        ""
+    }
+}
+
+fn zero_extend(digits: &str, precision: Option<usize>) -> String {
+    match precision {
+        // Precision 0 prints nothing at all for a zero
+        Some(0) if digits == "0" => String::new(),
+        Some(precision) => "0".repeat(precision.saturating_sub(digits.len())) + digits,
+        None => digits.to_string(),
+    }
+}
+
+fn pad_number(spec: &Spec, sign: &str, prefix: &str, digits: &str, out: &mut Vec<u8>) {
+    let digits = zero_extend(digits, spec.precision);
+    pad(spec, &format!("{}{}", sign, prefix), digits.as_bytes(), spec.precision.is_none(), out);
+}
+
+// Pads `prefix` and `body` to the width: on the right with `-`, with zeros
+// between them with `0` where that is allowed, and otherwise on the left
+fn pad(spec: &Spec, prefix: &str, body: &[u8], zero_ok: bool, out: &mut Vec<u8>) {
+    let fill = spec.width.saturating_sub(prefix.len() + body.len());
+    if spec.left {
+        out.extend_from_slice(prefix.as_bytes());
+        out.extend_from_slice(body);
+        out.extend(std::iter::repeat_n(b' ', fill));
+    } else if spec.zero && zero_ok {
+        out.extend_from_slice(prefix.as_bytes());
+        out.extend(std::iter::repeat_n(b'0', fill));
+        out.extend_from_slice(body);
+    } else {
+        out.extend(std::iter::repeat_n(b' ', fill));
+        out.extend_from_slice(prefix.as_bytes());
+        out.extend_from_slice(body);
+    }
+}
+
+fn truncate(bytes: &[u8], precision: Option<usize>) -> &[u8] {
+    match precision {
+        Some(precision) if precision < bytes.len() => &bytes[..precision],
+        _ => bytes,
+    }
+}
+
+// Formats a non-negative value for %f, %e or %g and their uppercase forms
+fn format_float(value: f64, conversion: u8, spec: &Spec) -> String {
+    let upper = conversion.is_ascii_uppercase();
+    if !value.is_finite() {
+        let text = if value.is_nan() { "nan" } else { "inf" };
+        return if upper { text.to_uppercase() } else { text.to_string() };
+    }
+    let precision = spec.precision.unwrap_or(6);
+    let mut text = match conversion.to_ascii_lowercase() {
+        b'f' => format_fixed(value, precision),
+        b'e' => format_exponent(value, precision),
+        _ => {
+            // %g picks %e or %f by the exponent, then drops trailing zeros
+            let precision = precision.max(1);
+            let exponent = exponent_of(value, precision - 1);
+            let text = if exponent < -4 || exponent >= precision as i32 {
+                format_exponent(value, precision - 1)
+            } else {
+                format_fixed(value, (precision as i64 - 1 - exponent as i64) as usize)
+            };
+            if spec.alt { text } else { strip_zeros(&text) }
+        }
+    };
+    if spec.alt && !text.contains('.') {
+        let at = text.find('e').unwrap_or(text.len());
+        text.insert(at, '.');
+    }
+    if upper { text.to_uppercase() } else { text }
+}
+
+fn format_fixed(value: f64, precision: usize) -> String {
+    let mut text = format!("{:.*}", precision.min(EXACT_DIGITS), value);
+    text.extend(std::iter::repeat_n('0', precision.saturating_sub(EXACT_DIGITS)));
+    text
+}
+
+// C writes the exponent with a sign and at least two digits: 1.5e+03
+fn format_exponent(value: f64, precision: usize) -> String {
+    let text = format!("{:.*e}", precision.min(EXACT_DIGITS), value);
+    let (mantissa, exponent) = text.split_once('e').unwrap();
+    let zeros = "0".repeat(precision.saturating_sub(EXACT_DIGITS));
+    let exponent: i32 = exponent.parse().unwrap();
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}{}e{}{:02}", mantissa, zeros, sign, exponent.abs())
+}
+
+fn exponent_of(value: f64, precision: usize) -> i32 {
+    let text = format!("{:.*e}", precision.min(EXACT_DIGITS), value);
+    text.split_once('e').unwrap().1.parse().unwrap()
+}
+
+fn strip_zeros(text: &str) -> String {
+    let (mantissa, exponent) = match text.find('e') {
+        Some(at) => text.split_at(at),
+        None => (text, ""),
+    };
+    let mantissa = match mantissa.contains('.') {
+        true => mantissa.trim_end_matches('0').trim_end_matches('.'),
+        false => mantissa,
+    };
+    format!("{}{}", mantissa, exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(format: &str, args: &[&str]) -> (String, bool) {
+        let args: Vec<OsString> = args.iter().map(OsString::from).collect();
+        let mut out = vec![];
+        let ok = printf(format.as_bytes(), &args, &mut out);
+        (String::from_utf8(out).unwrap(), ok)
+    }
+
+    fn ok(format: &str, args: &[&str]) -> String {
+        let (out, ok) = run(format, args);
+        assert!(ok, "{:?} failed", format);
+        out
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(ok("%d %i %5d|%-5d|%05d", &["42", "-7", "3", "3", "-42"]), "42 -7     3|3    |-0042");
+        assert_eq!(ok("%+d % d %.3d %.0d|", &["5", "5", "7", "0"]), "+5  5 007 |");
+        assert_eq!(ok("%d %d %d", &["0x1f", "010", "'A"]), "31 8 65");
+        assert_eq!(ok("%u %x %X %o", &["-1", "255", "255", "8"]), "18446744073709551615 ff FF 10");
+        assert_eq!(ok("%#x %#o %#x", &["255", "8", "0"]), "0xff 010 0");
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(ok("%f %.2f %e %E", &["3.14159265", "2.005", "12345.678", "0.5"]), "3.141593 2.00 1.234568e+04 5.000000E-01");
+        assert_eq!(
+            ok("%g %g %g %g %g %G", &["100000", "1000000", "0.0001", "0.00001", "1.5", "1e-10"]),
+            "100000 1e+06 0.0001 1e-05 1.5 1E-10"
+        );
+        assert_eq!(ok("%#g|%10.3e|%-10.1f|%+f|%08.2f", &["1", "1234.5", "2.25", "inf", "-3.14159"]), "1.00000| 1.234e+03|2.2       |+inf|-0003.14");
+        assert_eq!(ok("%.0f %#.0f %f", &["2.5", "2", "-0"]), "2 2. -0.000000");
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(ok("%5.2s|%-5s|%s|", &["abc", "ab", ""]), "   ab|ab   ||");
+        assert_eq!(ok("%c%c|%3c", &["xyz", "", "z"]), "x\0|  z");
+        assert_eq!(ok("%*d|%.*f|%-*s|", &["5", "42", "2", "3.14159", "-3", "a"]), "   42|3.14|a  |");
+        assert_eq!(ok("%%|%b|%s", &[r"a\tb\0101", r"a\tb"]), "%|a\tbA|a\\tb");
+    }
+
+    #[test]
+    fn test_format_escapes() {
+        assert_eq!(ok(r#"a\tb\101\x41\q\"\n"#, &[]), "a\tbAA\\q\"\n");
+        assert_eq!(ok(r"one\ctwo", &[]), "one");
+        assert_eq!(ok("%b|%s", &[r"stop\chere", "never"]), "stop");
+    }
+
+    #[test]
+    fn test_recycling() {
+        assert_eq!(ok("%s-%s\n", &["a", "b", "c"]), "a-b\nc-\n");
+        assert_eq!(ok("%d\n", &[]), "0\n");
+        // Nothing is consumed, so the format is used once
+        assert_eq!(ok("x\n", &["a", "b"]), "x\n");
+    }
+
+    #[test]
+    fn test_bad_numbers() {
+        assert_eq!(run("%d|%d|%d", &["abc", "3abc", "99999999999999999999"]), ("0|3|9223372036854775807".to_string(), false));
+        assert_eq!(run("%f", &["3.5abc"]), ("3.500000".to_string(), false));
+        assert_eq!(run("%d", &[""]), ("0".to_string(), true));
+        assert_eq!(run("%d|%d", &["'", "'ab"]), ("0|97".to_string(), false));
+        assert_eq!(ok("%g %g", &["0x1p3", "-0x1.8"]), "8 -1.5");
+        assert_eq!(run("%g", &["0x"]), ("0".to_string(), false));
+    }
+
+    #[test]
+    fn test_huge_precision() {
+        let out = ok("%.70000f", &["1"]);
+        assert_eq!(out.len(), 70002);
+        assert!(out.starts_with("1.000") && out.ends_with("000"));
+        let out = ok("%.*e", &["70000", "1.5"]);
+        assert_eq!(out.len(), 70006);
+        assert!(out.starts_with("1.5000") && out.ends_with("0e+00"));
+        assert_eq!(ok("%.70000g", &["0.5"]), "0.5");
+        assert_eq!(ok("%.70000d", &["7"]).len(), 70000);
+        assert_eq!(ok("%.2000f", &["0.1"]), format!("{:.1100}{}", 0.1, "0".repeat(900)));
+    }
+
+    #[test]
+    fn test_field_out_of_range() {
+        for format in ["%999999999999999999999d", "%2147483648s", "%.2147483648f", "%*d", "%.*d"] {
+            let (out, ok) = run(format, &["99999999999", "1"]);
+            assert_eq!((out.as_str(), ok), ("", false), "{}", format);
+        }
+        assert_eq!(run("%*d|", &["-3", "1"]), ("1  |".to_string(), true));
+    }
+
+    #[test]
+    fn test_invalid_conversion() {
+        assert_eq!(run("ab%y", &["a"]), ("ab".to_string(), false));
+        assert_eq!(run("ab%", &[]), ("ab".to_string(), false));
+        assert_eq!(run("%5%", &[]), ("".to_string(), false));
+        for format in ["%5b", "%#s", "%05s", "%.3c", "%#d", "%'x"] {
+            assert_eq!(run(format, &["1"]), ("".to_string(), false), "{}", format);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+/// `--color always|never|auto`: whether styles are written at all.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum When {
+    Always,
+    Never,
+    /// Only when stdout is a terminal, unless the environment says otherwise
+    #[default]
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    /// One of the 16 basic colors: `red`, `bright-red`, ...
+    Basic(u8),
+    /// A color of the 256-color palette: `0` to `255`
+    Indexed(u8),
+    /// A truecolor `#rrggbb`
+    Rgb(u8, u8, u8),
+}
+
+const NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+impl Color {
+    pub fn parse(name: &str) -> Option<Color> {
+        if let Some(index) = NAMES.iter().position(|&n| n == name) {
+            return Some(Color::Basic(index as u8));
+        }
+        if let Some(bright) = name.strip_prefix("bright-") {
+            let index = NAMES.iter().position(|&n| n == bright)?;
+            return Some(Color::Basic(index as u8 + 8));
+        }
+        if let Some(hex) = name.strip_prefix('#') {
+            if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+        if !name.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        name.parse().ok().map(Color::Indexed)
+    }
+
+    // The SGR parameters, with `base` 30 for the foreground and 40 for the
+    // background
+    fn sgr(self, base: u8) -> String {
+        match self {
+            Color::Basic(index) if index < 8 => (base + index).to_string(),
+            Color::Basic(index) => (base + 60 + index - 8).to_string(),
+            Color::Indexed(index) => format!("{};5;{}", base + 8, index),
+            Color::Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+        }
+    }
+}
+
+/// The attributes the text is printed with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub bold: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    /// The escape sequence that turns the style on, or None for no style.
+    pub fn start(&self) -> Option<String> {
+        let mut params = vec![];
+        if self.bold {
+            params.push("1".to_string());
+        }
+        if self.underline {
+            params.push("4".to_string());
+        }
+        params.extend(self.foreground.map(|color| color.sgr(30)));
+        params.extend(self.background.map(|color| color.sgr(40)));
+        (!params.is_empty()).then(|| format!("\x1b[{}m", params.join(";")))
+    }
+}
+
+/// The escape sequence that turns every style off.
+pub const RESET: &str = "\x1b[0m";
+
+/// Whether to write styles. Besides `--color`, `NO_COLOR` turns them off
+/// and `FORCE_COLOR` turns them on even when stdout is not a terminal.
+pub fn enabled(when: When) -> bool {
+    let set = |name| env::var_os(name).is_some_and(|value| !value.is_empty());
+    match when {
+        When::Always => true,
+        When::Never => false,
+        When::Auto if set("NO_COLOR") => false,
+        When::Auto => set("FORCE_COLOR") || io::stdout().is_terminal(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Color::parse("red"), Some(Color::Basic(1)));
+        assert_eq!(Color::parse("bright-white"), Some(Color::Basic(15)));
+        assert_eq!(Color::parse("208"), Some(Color::Indexed(208)));
+        assert_eq!(Color::parse("#ff8000"), Some(Color::Rgb(255, 128, 0)));
+        for bad in ["", "pink", "bright-", "256", "-1", "+1", "#fff", "#gg0000"] {
+            assert_eq!(Color::parse(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_start() {
+        assert_eq!(Style::default().start(), None);
+        let style = Style {
+            foreground: Color::parse("bright-red"),
+            background: Color::parse("blue"),
+            bold: true,
+            underline: true,
+        };
+        assert_eq!(style.start().unwrap(), "\x1b[1;4;91;44m");
+        let style = Style {
+            foreground: Color::parse("208"),
+            background: Color::parse("#010203"),
+            ..Style::default()
+        };
+        assert_eq!(style.start().unwrap(), "\x1b[38;5;208;48;2;1;2;3m");
+    }
+}
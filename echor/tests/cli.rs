@@ -1,20 +1,17 @@
-use assert_cmd::Command;
+use assert_cmd::{Command, assert::OutputAssertExt, cargo::CommandCargoExt};
 use predicates::prelude::*;
-use std::fs;
+use std::{
+    fs,
+    process::{self, Stdio},
+};
+#[cfg(unix)]
+use std::{ffi::OsStr, fs::File, os::unix::ffi::OsStrExt};
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
 #[test]
-fn dies_no_args() -> TestResult {
-    // let mut cmd = Command::cargo_bin("echor").unwrap();
-    // cmd.assert()
-    //     .failure()
-    //     .stderr(predicate::str::contains("Usage"));
-    Command::cargo_bin("echor")?
-        .assert()
-        .failure()
-        .stderr(predicate::str::contains("Usage"));
-    Ok(())
+fn no_args() -> TestResult {
+    run(&[], "tests/expected/empty.txt")
 }
 
 // #[test]
@@ -67,10 +64,515 @@ fn hello2() -> TestResult {
 
 #[test]
 fn hello1_no_newline() -> TestResult {
-    run(&["Hello  there", "-n"], "tests/expected/hello1.n.txt")
+    run(&["-n", "Hello  there"], "tests/expected/hello1.n.txt")
 }
 
 #[test]
 fn hello2_no_newline() -> TestResult {
     run(&["-n", "Hello", "there"], "tests/expected/hello2.n.txt")
-}
\ No newline at end of file
+}
+#[test]
+fn escape_backslash() -> TestResult {
+    run(&["-e", r"back\\slash"], "tests/expected/escape.backslash.txt")
+}
+
+#[test]
+fn escape_alert() -> TestResult {
+    run(&["-e", r"bell\a"], "tests/expected/escape.a.txt")
+}
+
+#[test]
+fn escape_backspace() -> TestResult {
+    run(&["-e", r"back\bspace"], "tests/expected/escape.b.txt")
+}
+
+#[test]
+fn escape_stop() -> TestResult {
+    run(&["-e", r"stop\chere", "never"], "tests/expected/escape.c.txt")
+}
+
+#[test]
+fn escape_escape() -> TestResult {
+    run(&["-e", r"\e[1mbold\e[0m"], "tests/expected/escape.e.txt")
+}
+
+#[test]
+fn escape_form_feed() -> TestResult {
+    run(&["-e", r"form\ffeed"], "tests/expected/escape.f.txt")
+}
+
+#[test]
+fn escape_newline() -> TestResult {
+    run(&["-e", r"new\nline"], "tests/expected/escape.n.txt")
+}
+
+#[test]
+fn escape_carriage_return() -> TestResult {
+    run(&["-e", r"carriage\rreturn"], "tests/expected/escape.r.txt")
+}
+
+#[test]
+fn escape_tab() -> TestResult {
+    run(&["-e", r"tab\there"], "tests/expected/escape.t.txt")
+}
+
+#[test]
+fn escape_vertical_tab() -> TestResult {
+    run(&["-e", r"vertical\vtab"], "tests/expected/escape.v.txt")
+}
+
+#[test]
+fn escape_octal() -> TestResult {
+    run(&["-e", r"octal \0101\0102"], "tests/expected/escape.0nnn.txt")
+}
+
+#[test]
+fn escape_hex() -> TestResult {
+    run(&["-e", r"hex \x41\x4a"], "tests/expected/escape.xhh.txt")
+}
+
+#[test]
+fn escape_unknown() -> TestResult {
+    run(&["-e", r"unknown \q"], "tests/expected/escape.unknown.txt")
+}
+
+#[test]
+fn escapes_disabled() -> TestResult {
+    run(&["-E", r"tab\there"], "tests/expected/escape.E.txt")
+}
+
+#[test]
+fn escapes_last_flag_wins() -> TestResult {
+    run(&["-e", "-E", r"tab\there"], "tests/expected/escape.E.txt")?;
+    run(&["-E", "-e", r"tab\there"], "tests/expected/escape.t.txt")
+}
+
+#[test]
+fn unknown_option_is_text() -> TestResult {
+    run(&["-x", "foo"], "tests/expected/dash.x.txt")
+}
+
+#[test]
+fn double_dash_is_text() -> TestResult {
+    run(&["--", "foo"], "tests/expected/dash.dash.txt")
+}
+
+#[test]
+fn options_after_text_are_text() -> TestResult {
+    run(&["Hello  there", "-n"], "tests/expected/hello1.dash_n.txt")
+}
+
+#[test]
+fn combined_options() -> TestResult {
+    run(&["-ne", r"tab\there"], "tests/expected/escape.t.n.txt")
+}
+
+#[test]
+fn help() -> TestResult {
+    Command::cargo_bin("echor")?
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Usage: echor [SHORT-OPTION]..."));
+    Ok(())
+}
+
+#[test]
+fn version() -> TestResult {
+    Command::cargo_bin("echor")?
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout("echor 0.1.0\n");
+    Ok(())
+}
+
+#[test]
+fn help_with_other_args_is_text() -> TestResult {
+    run(&["--help", "me"], "tests/expected/dash.help.txt")
+}
+
+#[test]
+fn printf_recycles_format() -> TestResult {
+    run(
+        &["--printf", r"%s=%5d|%-5s|%05.1f\n", "a", "42", "left", "3.14159", "b", "-7", "x", "2"],
+        "tests/expected/printf.recycle.txt",
+    )
+}
+
+#[test]
+fn printf_conversions() -> TestResult {
+    run(
+        &["--printf", r"%x %o %e %c %%\n", "255", "8", "1234.5", "word"],
+        "tests/expected/printf.conversions.txt",
+    )
+}
+
+#[test]
+fn printf_b_interprets_escapes() -> TestResult {
+    run(
+        &["--printf", r"%b|%s\n", r"tab\there", r"tab\there"],
+        "tests/expected/printf.b.txt",
+    )
+}
+
+#[test]
+fn printf_bad_number() -> TestResult {
+    Command::cargo_bin("echor")?
+        .args(["--printf", r"%d|%d\n", "12abc", "7"])
+        .assert()
+        .failure()
+        .stdout("12|7\n")
+        .stderr("echor: '12abc': value not completely converted\n");
+    Ok(())
+}
+
+#[test]
+fn printf_invalid_conversion() -> TestResult {
+    Command::cargo_bin("echor")?
+        .args(["--printf", "a%ya"])
+        .assert()
+        .failure()
+        .stdout("a")
+        .stderr("echor: %y: invalid conversion specification\n");
+    Ok(())
+}
+
+#[test]
+fn dies_printf_without_format() -> TestResult {
+    Command::cargo_bin("echor")?
+        .arg("--printf")
+        .assert()
+        .failure()
+        .stderr("echor: missing operand\n");
+    Ok(())
+}
+
+#[test]
+fn separator_and_terminator() -> TestResult {
+    run(
+        &["--sep", ", ", "--end=;", "a", "b", "c"],
+        "tests/expected/sep.end.txt",
+    )
+}
+
+#[test]
+fn each_line_nul() -> TestResult {
+    run(&["-z", "--each-line", "a", "b", "c"], "tests/expected/each_line.z.txt")
+}
+
+#[test]
+fn nul_separator_without_terminator() -> TestResult {
+    run(&["-nz", "--sep", r"\0", "-e", "a", "b"], "tests/expected/z.n.txt")
+}
+
+#[test]
+fn separator_escape() -> TestResult {
+    run(&["-e", "--sep", r"\t", "a", "b"], "tests/expected/sep.escape.txt")
+}
+
+#[test]
+fn dies_sep_without_value() -> TestResult {
+    Command::cargo_bin("echor")?
+        .arg("--sep")
+        .assert()
+        .failure()
+        .stderr("echor: option '--sep' requires an argument\n");
+    Ok(())
+}
+
+#[test]
+fn color_forced() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env("FORCE_COLOR", "1")
+        .env_remove("NO_COLOR")
+        .args(["--color", "red", "--bold", "--bg", "#102030", "ok", "done"])
+        .assert()
+        .success()
+        .stdout("\x1b[1;31;48;2;16;32;48mok done\x1b[0m\n");
+    Ok(())
+}
+
+#[test]
+fn color_256_underline() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env("FORCE_COLOR", "1")
+        .env_remove("NO_COLOR")
+        .args(["-n", "--underline", "--color=208", "warn"])
+        .assert()
+        .success()
+        .stdout("\x1b[4;38;5;208mwarn\x1b[0m");
+    Ok(())
+}
+
+#[test]
+fn color_off_when_not_a_terminal() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env_remove("FORCE_COLOR")
+        .env_remove("NO_COLOR")
+        .args(["--color", "red", "plain"])
+        .assert()
+        .success()
+        .stdout("plain\n");
+    Ok(())
+}
+
+#[test]
+fn color_off_with_no_color() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env("FORCE_COLOR", "1")
+        .env("NO_COLOR", "1")
+        .args(["--color", "red", "plain"])
+        .assert()
+        .success()
+        .stdout("plain\n");
+    Ok(())
+}
+
+#[test]
+fn color_never() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env("FORCE_COLOR", "1")
+        .env_remove("NO_COLOR")
+        .args(["--bold", "--color=never", "plain"])
+        .assert()
+        .success()
+        .stdout("plain\n");
+    Ok(())
+}
+
+#[test]
+fn color_always() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env_remove("FORCE_COLOR")
+        .env_remove("NO_COLOR")
+        .args(["--color=always", "--color", "bright-green", "ok"])
+        .assert()
+        .success()
+        .stdout("\x1b[92mok\x1b[0m\n");
+    Ok(())
+}
+
+#[test]
+fn dies_bad_color() -> TestResult {
+    Command::cargo_bin("echor")?
+        .args(["--color", "pink", "text"])
+        .assert()
+        .failure()
+        .stderr("echor: invalid color 'pink'\n");
+    Ok(())
+}
+
+#[test]
+fn expand_variables() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env("GREETING", "hi")
+        .env_remove("NAME")
+        .args(["--expand", "${GREETING}", "${NAME:-there}", "$GREETING", "${NAME}!"])
+        .assert()
+        .success()
+        .stdout("hi there $GREETING !\n");
+    Ok(())
+}
+
+#[test]
+fn without_expand_text_is_literal() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env("GREETING", "hi")
+        .arg("${GREETING}")
+        .assert()
+        .success()
+        .stdout("${GREETING}\n");
+    Ok(())
+}
+
+#[test]
+fn dies_expand_required_variable() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env_remove("TOKEN")
+        .args(["--expand", "token=${TOKEN:?must be set}"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("echor: TOKEN: must be set\n");
+    Ok(())
+}
+
+#[test]
+fn template_file() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env("NAME", "Ferris")
+        .env("HOME_DIR", "/home/ferris")
+        .args(["--template", "tests/inputs/greeting.tmpl"])
+        .assert()
+        .success()
+        .stdout("Hello, Ferris!\nHome: /home/ferris\nCost: $5 and ${not a variable}\n");
+    Ok(())
+}
+
+#[test]
+fn template_stdin() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env_remove("NAME")
+        .args(["--template", "-"])
+        .write_stdin("[${NAME}] ${NAME:-nobody}")
+        .assert()
+        .success()
+        .stdout("[] nobody");
+    Ok(())
+}
+
+#[test]
+fn dies_template_strict() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env_remove("HOME_DIR")
+        .args(["--strict", "--template", "tests/inputs/greeting.tmpl"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("echor: HOME_DIR: unbound variable\n");
+    Ok(())
+}
+
+#[test]
+fn dies_template_missing_file() -> TestResult {
+    Command::cargo_bin("echor")?
+        .args(["--template", "tests/inputs/nope.tmpl"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with("echor: tests/inputs/nope.tmpl: "));
+    Ok(())
+}
+
+#[test]
+fn dies_template_with_text() -> TestResult {
+    Command::cargo_bin("echor")?
+        .args(["--template", "-", "extra"])
+        .assert()
+        .failure()
+        .stderr("echor: extra operand 'extra'\n");
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn dies_write_error() -> TestResult {
+    process::Command::cargo_bin("echor")?
+        .arg("no space")
+        .stdout(File::create("/dev/full")?)
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::starts_with("echor: write error: No space left"));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn dies_printf_write_error() -> TestResult {
+    process::Command::cargo_bin("echor")?
+        .args(["--printf", "%s", "no space"])
+        .stdout(File::create("/dev/full")?)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with("echor: write error: "));
+    Ok(())
+}
+
+#[test]
+fn broken_pipe_is_quiet() -> TestResult {
+    // More than a pipe holds, so the write can't finish before the reader
+    // is gone
+    let template = std::env::temp_dir().join(format!("echor-big-{}.txt", process::id()));
+    fs::write(&template, "x".repeat(1 << 20))?;
+    let mut child = process::Command::cargo_bin("echor")?
+        .arg("--template")
+        .arg(&template)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    drop(child.stdout.take());
+    let output = child.wait_with_output()?;
+    fs::remove_file(&template)?;
+    output.assert().code(141).stderr("");
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_passthrough() -> TestResult {
+    // "café" and "naïve" in Latin-1
+    Command::cargo_bin("echor")?
+        .arg(OsStr::from_bytes(b"caf\xe9"))
+        .arg(OsStr::from_bytes(b"na\xefve"))
+        .assert()
+        .success()
+        .stdout(b"caf\xe9 na\xefve\n".as_slice());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_options() -> TestResult {
+    Command::cargo_bin("echor")?
+        .arg("-e")
+        .arg(OsStr::from_bytes(b"--sep=\xff"))
+        .arg(OsStr::from_bytes(b"\xe9\\x41"))
+        .arg("b")
+        .assert()
+        .success()
+        .stdout(b"\xe9A\xffb\n".as_slice());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_printf() -> TestResult {
+    Command::cargo_bin("echor")?
+        .args(["--printf", "[%s] %d\\n"])
+        .arg(OsStr::from_bytes(b"\xe9t\xe9"))
+        .arg(OsStr::from_bytes(b"'\xe9"))
+        .assert()
+        .success()
+        .stdout(b"[\xe9t\xe9] 233\n".as_slice());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_expand() -> TestResult {
+    Command::cargo_bin("echor")?
+        .env("CITY", OsStr::from_bytes(b"M\xfcnchen"))
+        .arg("--expand")
+        .arg(OsStr::from_bytes(b"\xbb ${CITY}"))
+        .assert()
+        .success()
+        .stdout(b"\xbb M\xfcnchen\n".as_slice());
+    Ok(())
+}
+
+#[test]
+fn printf_huge_precision() -> TestResult {
+    let output = Command::cargo_bin("echor")?
+        .args(["--printf", "%.70000f", "1"])
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout.len(), 70002);
+    Ok(())
+}
+
+#[test]
+fn dies_printf_huge_width() -> TestResult {
+    Command::cargo_bin("echor")?
+        .args(["--printf", "%999999999999999999999d", "1"])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("echor: invalid field width: '999999999999999999999'\n");
+    Command::cargo_bin("echor")?
+        .args(["--printf", "%.*f", "99999999999", "1"])
+        .assert()
+        .code(1)
+        .stderr("echor: invalid precision: '99999999999'\n");
+    Ok(())
+}
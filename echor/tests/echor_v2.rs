@@ -1,6 +1,11 @@
-use assert_cmd::Command;
+use assert_cmd::{Command, assert::OutputAssertExt, cargo::CommandCargoExt};
 use predicates::prelude::*;
-use std::fs;
+use std::{
+    fs,
+    process::{self, Stdio},
+};
+#[cfg(unix)]
+use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
@@ -73,4 +78,29 @@ fn hello1_no_newline() -> TestResult {
 #[test]
 fn hello2_no_newline() -> TestResult {
     run(&["-n", "Hello", "there"], "tests/expected/hello2.n.txt")
-}
\ No newline at end of file
+}
+#[cfg(unix)]
+#[test]
+fn non_utf8_passthrough() -> TestResult {
+    Command::cargo_bin("echor_v2")?
+        .arg(OsStr::from_bytes(b"caf\xe9"))
+        .arg("-n")
+        .assert()
+        .success()
+        .stdout(b"caf\xe9".as_slice());
+    Ok(())
+}
+
+#[test]
+fn broken_pipe_is_quiet() -> TestResult {
+    // More than a pipe holds, but less than the limit for one argument
+    let mut child = process::Command::cargo_bin("echor_v2")?
+        .arg("x".repeat(100_000))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    drop(child.stdout.take());
+    let output = child.wait_with_output()?;
+    output.assert().code(141).stderr("");
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use std::{
+    env,
+    io::{self, Write},
+    process,
+};
+
+const HELP: &str = "\
+Usage: false [ignored command line arguments]
+  or:  false OPTION
+Exit with a status code indicating failure.
+
+      --help     display this help and exit
+      --version  output version information and exit
+";
+
+fn main() {
+    // As in GNU false, a lone --help or --version is the only thing that
+    // isn't ignored, and the status is 1 even then
+    let args: Vec<_> = env::args_os().skip(1).collect();
+    let text = match args.as_slice() {
+        [arg] if arg == "--help" => HELP.to_string(),
+        [arg] if arg == "--version" => format!("false {}\n", env!("CARGO_PKG_VERSION")),
+        _ => String::new(),
+    };
+    if let Err(e) = io::stdout().write_all(text.as_bytes()) {
+        eprintln!("false: write error: {}", e);
+    }
+    process::exit(1);
+}
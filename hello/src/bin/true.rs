@@ -1,3 +1,29 @@
+use std::{
+    env,
+    io::{self, Write},
+    process,
+};
+
+const HELP: &str = "\
+Usage: true [ignored command line arguments]
+  or:  true OPTION
+Exit with a status code indicating success.
+
+      --help     display this help and exit
+      --version  output version information and exit
+";
+
 fn main() {
-    // std::process::exit(0);
-}
\ No newline at end of file
+    // As in GNU true, a lone --help or --version is the only thing that
+    // isn't ignored
+    let args: Vec<_> = env::args_os().skip(1).collect();
+    let text = match args.as_slice() {
+        [arg] if arg == "--help" => HELP.to_string(),
+        [arg] if arg == "--version" => format!("true {}\n", env!("CARGO_PKG_VERSION")),
+        _ => return,
+    };
+    if let Err(e) = io::stdout().write_all(text.as_bytes()) {
+        eprintln!("true: write error: {}", e);
+        process::exit(1);
+    }
+}
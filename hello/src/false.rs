@@ -1,4 +0,0 @@
-fn main() {
-    // std::process::exit(1);
-    std::process::abort();
-}
\ No newline at end of file
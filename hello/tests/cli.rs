@@ -25,11 +25,67 @@ fn runs() {
 #[test]
 fn true_ok() {
     let mut cmd = Command::cargo_bin("true").unwrap();
-    cmd.assert().success();
+    cmd.assert().code(0).stdout("").stderr("");
+}
+
+#[test]
+fn true_ignores_args() {
+    let mut cmd = Command::cargo_bin("true").unwrap();
+    cmd.args(["--help", "-x"]).assert().code(0).stdout("");
+}
+
+#[test]
+fn true_help() {
+    let mut cmd = Command::cargo_bin("true").unwrap();
+    cmd.arg("--help")
+        .assert()
+        .code(0)
+        .stdout(concat!(
+            "Usage: true [ignored command line arguments]\n",
+            "  or:  true OPTION\n",
+            "Exit with a status code indicating success.\n",
+            "\n",
+            "      --help     display this help and exit\n",
+            "      --version  output version information and exit\n",
+        ));
+}
+
+#[test]
+fn true_version() {
+    let mut cmd = Command::cargo_bin("true").unwrap();
+    cmd.arg("--version").assert().code(0).stdout("true 0.1.0\n");
 }
 
 #[test]
 fn false_not_ok() {
     let mut cmd = Command::cargo_bin("false").unwrap();
-    cmd.assert().failure();
+    cmd.assert().code(1).stdout("").stderr("");
+}
+
+#[test]
+fn false_ignores_args() {
+    let mut cmd = Command::cargo_bin("false").unwrap();
+    cmd.args(["--version", "-x"]).assert().code(1).stdout("");
+}
+
+#[test]
+fn false_help() {
+    let mut cmd = Command::cargo_bin("false").unwrap();
+    cmd.arg("--help")
+        .assert()
+        .code(1)
+        .stdout(concat!(
+            "Usage: false [ignored command line arguments]\n",
+            "  or:  false OPTION\n",
+            "Exit with a status code indicating failure.\n",
+            "\n",
+            "      --help     display this help and exit\n",
+            "      --version  output version information and exit\n",
+        ));
+}
+
+#[test]
+fn false_version() {
+    let mut cmd = Command::cargo_bin("false").unwrap();
+    cmd.arg("--version").assert().code(1).stdout("false 0.1.0\n");
 }
\ No newline at end of file
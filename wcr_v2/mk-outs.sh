@@ -0,0 +1,45 @@
+#!/usr/bin/env bash
+
+# Regenerate the expected outputs with GNU wc, whose column widths we match.
+
+ROOT="tests/inputs"
+OUT_DIR="tests/expected"
+
+[[ ! -d "$OUT_DIR" ]] && mkdir -p "$OUT_DIR"
+
+export LC_ALL=C.UTF-8
+
+EMPTY="$ROOT/empty.txt"
+FOX="$ROOT/fox.txt"
+ATLAMAL="$ROOT/atlamal.txt"
+ALL="$EMPTY $FOX $ATLAMAL"
+
+for FILE in $EMPTY $FOX $ATLAMAL; do
+    BASENAME=$(basename "$FILE")
+    wc       $FILE > ${OUT_DIR}/${BASENAME}.out
+    wc -l    $FILE > ${OUT_DIR}/${BASENAME}.l.out
+    wc -w    $FILE > ${OUT_DIR}/${BASENAME}.w.out
+    wc -c    $FILE > ${OUT_DIR}/${BASENAME}.c.out
+    wc -m    $FILE > ${OUT_DIR}/${BASENAME}.m.out
+    wc -lwm  $FILE > ${OUT_DIR}/${BASENAME}.lwm.out
+    wc -wc   $FILE > ${OUT_DIR}/${BASENAME}.wc.out
+    wc -wl   $FILE > ${OUT_DIR}/${BASENAME}.wl.out
+    wc -wm   $FILE > ${OUT_DIR}/${BASENAME}.wm.out
+    wc -cl   $FILE > ${OUT_DIR}/${BASENAME}.cl.out
+    wc -ml   $FILE > ${OUT_DIR}/${BASENAME}.ml.out
+done
+
+wc      $ALL > $OUT_DIR/all.out
+wc -l   $ALL > $OUT_DIR/all.l.out
+wc -w   $ALL > $OUT_DIR/all.w.out
+wc -c   $ALL > $OUT_DIR/all.c.out
+wc -m   $ALL > $OUT_DIR/all.m.out
+wc -lwm $ALL > $OUT_DIR/all.lwm.out
+wc -wc  $ALL > $OUT_DIR/all.wc.out
+wc -wl  $ALL > $OUT_DIR/all.wl.out
+wc -wm  $ALL > $OUT_DIR/all.wm.out
+wc -cl  $ALL > $OUT_DIR/all.cl.out
+wc -ml  $ALL > $OUT_DIR/all.ml.out
+
+wc < $ATLAMAL > $OUT_DIR/atlamal.txt.stdin.out
+cat $ATLAMAL | wc > $OUT_DIR/atlamal.txt.pipe.out
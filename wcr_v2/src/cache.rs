@@ -0,0 +1,296 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, Metadata},
+    io,
+    path::{self, Path, PathBuf},
+    process,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::FileInfo;
+
+/// The maintenance commands that go with `--cache`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheCommand {
+    /// Remove every entry.
+    Clear,
+    /// Remove the entries of the given files.
+    Invalidate,
+    /// Remove the entries of files that changed or are gone.
+    Gc,
+}
+
+/// Identifies one version of a file, counted with one set of options.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Key {
+    dev: u64,
+    ino: u64,
+    size: u64,
+    mtime_secs: u64,
+    mtime_nanos: u32,
+    flags: String,
+}
+
+impl Key {
+    fn new(metadata: &Metadata, flags: &str) -> Option<Self> {
+        let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        let (dev, ino) = dev_ino(metadata);
+        Some(Key {
+            dev,
+            ino,
+            size: metadata.len(),
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+            flags: flags.to_string(),
+        })
+    }
+
+    // The entry file name leaves out size and mtime, so a changed file
+    // overwrites its old entry instead of leaving it behind
+    fn file_name(&self) -> String {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let bytes = self
+            .dev
+            .to_le_bytes()
+            .into_iter()
+            .chain(self.ino.to_le_bytes())
+            .chain(self.flags.bytes());
+        for byte in bytes {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3);
+        }
+        format!("{:016x}.json", hash)
+    }
+}
+
+#[cfg(unix)]
+fn dev_ino(metadata: &Metadata) -> (u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.dev(), metadata.ino())
+}
+
+// Without inodes the stored path is all that tells files apart
+#[cfg(not(unix))]
+fn dev_ino(_metadata: &Metadata) -> (u64, u64) {
+    (0, 0)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    path: PathBuf,
+    key: Key,
+    info: FileInfo,
+}
+
+/// Counts stored in a directory by `--cache`, one JSON file per entry.
+#[derive(Debug)]
+pub struct Cache {
+    dir: PathBuf,
+    hits: usize,
+    misses: usize,
+}
+
+impl Cache {
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Cache {
+            dir: dir.as_ref().to_path_buf(),
+            hits: 0,
+            misses: 0,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// The stored counts of `filename`, if it is a regular file that has
+    /// not changed since it was counted with the same `flags`.
+    pub fn get(&mut self, filename: &str, flags: &str) -> Option<FileInfo> {
+        let (path, key) = lookup(filename, flags)?;
+        let entry = self.read(&self.dir.join(key.file_name())).ok();
+        match entry {
+            Some(entry) if entry.key == key && entry.path == path => {
+                self.hits += 1;
+                Some(entry.info)
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Stores the counts of `filename`. Like git's racy-clean check, a file
+    /// modified within the last second is left out, since a write in the same
+    /// clock tick would not change its mtime.
+    pub fn put(&self, filename: &str, flags: &str, info: &FileInfo) -> io::Result<()> {
+        let Some((path, key)) = lookup(filename, flags) else {
+            return Ok(());
+        };
+        let mtime = UNIX_EPOCH + Duration::new(key.mtime_secs, key.mtime_nanos);
+        if SystemTime::now().duration_since(mtime).unwrap_or_default() < Duration::from_secs(1) {
+            return Ok(());
+        }
+        let target = self.dir.join(key.file_name());
+        let entry = Entry {
+            path,
+            key,
+            info: info.clone(),
+        };
+        // Write and rename so concurrent runs never read half an entry
+        let temp = target.with_extension(format!("tmp{}", process::id()));
+        fs::write(&temp, serde_json::to_vec(&entry)?)?;
+        fs::rename(&temp, &target)
+    }
+
+    /// Removes every entry and returns how many there were.
+    pub fn clear(&self) -> io::Result<usize> {
+        self.remove_where(|_| true)
+    }
+
+    /// Removes the entries of `files`, whatever options they were counted with.
+    pub fn invalidate(&self, files: &[String]) -> io::Result<usize> {
+        let paths: Vec<PathBuf> = files
+            .iter()
+            .filter(|filename| *filename != "-")
+            .filter_map(|filename| {
+                fs::canonicalize(filename)
+                    .or_else(|_| path::absolute(filename))
+                    .ok()
+            })
+            .collect();
+        self.remove_where(|entry| entry.is_some_and(|entry| paths.contains(&entry.path)))
+    }
+
+    /// Removes the entries of files that changed or no longer exist, and
+    /// any entry that can't be read. Files the cache didn't write are left
+    /// alone.
+    pub fn gc(&self) -> io::Result<usize> {
+        self.remove_where(|entry| match entry {
+            Some(entry) => {
+                let current = fs::metadata(&entry.path)
+                    .ok()
+                    .and_then(|metadata| Key::new(&metadata, &entry.key.flags));
+                current.as_ref() != Some(&entry.key)
+            }
+            None => true,
+        })
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Entry> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    // Only looks at files named the way `Key::file_name` names them, so
+    // that a cache directory shared with other files never loses them
+    fn remove_where(&self, stale: impl Fn(Option<&Entry>) -> bool) -> io::Result<usize> {
+        let mut removed = 0;
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            if !path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(is_entry_name)
+            {
+                continue;
+            }
+            if stale(self.read(&path).ok().as_ref()) {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+fn is_entry_name(name: &str) -> bool {
+    name.strip_suffix(".json").is_some_and(|hash| {
+        hash.len() == 16 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    })
+}
+
+fn lookup(filename: &str, flags: &str) -> Option<(PathBuf, Key)> {
+    if filename == "-" {
+        return None;
+    }
+    let metadata = fs::metadata(filename).ok().filter(Metadata::is_file)?;
+    Some((
+        fs::canonicalize(filename).ok()?,
+        Key::new(&metadata, flags)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    fn counted(path: &Path) -> FileInfo {
+        crate::Counter::new()
+            .count(io::BufReader::new(File::open(path).unwrap()))
+            .unwrap()
+    }
+
+    fn backdate(path: &Path) {
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(60))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_get_and_put() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        let name = path.to_str().unwrap();
+        fs::write(&path, "one two\n").unwrap();
+        let mut cache = Cache::open(dir.path().join("cache")).unwrap();
+
+        // Just written, so too fresh to store
+        cache.put(name, "flags", &counted(&path)).unwrap();
+        assert_eq!(cache.get(name, "flags"), None);
+
+        backdate(&path);
+        cache.put(name, "flags", &counted(&path)).unwrap();
+        assert_eq!(cache.get(name, "flags"), Some(counted(&path)));
+        assert_eq!(cache.get(name, "other flags"), None);
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+
+        fs::write(&path, "one two three\n").unwrap();
+        assert_eq!(cache.get(name, "flags"), None);
+    }
+
+    #[test]
+    fn test_maintenance() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::open(dir.path().join("cache")).unwrap();
+        let mut names = vec![];
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            let path = dir.path().join(name);
+            fs::write(&path, name).unwrap();
+            backdate(&path);
+            let name = path.to_str().unwrap().to_string();
+            cache.put(&name, "x", &counted(&path)).unwrap();
+            cache.put(&name, "y", &counted(&path)).unwrap();
+            names.push(name);
+        }
+
+        assert_eq!(cache.invalidate(&names[..1]).unwrap(), 2);
+        fs::remove_file(&names[1]).unwrap();
+        let junk = dir.path().join("cache").join("junk.json");
+        fs::write(&junk, "{").unwrap();
+        let corrupt = dir.path().join("cache").join("0123456789abcdef.json");
+        fs::write(&corrupt, "{").unwrap();
+        assert_eq!(cache.gc().unwrap(), 3);
+        assert!(!corrupt.exists());
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert!(junk.exists());
+    }
+}
@@ -0,0 +1,599 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    io::{self, BufRead, Write},
+    iter::Sum,
+    ops::AddAssign,
+};
+
+use crate::{metrics, Distribution, Metric, WcError, Whitespace, WordSplitter};
+
+/// The counts for one input, or the sum of several.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileInfo {
+    num_lines: usize,
+    num_words: usize,
+    num_bytes: usize,
+    num_chars: usize,
+    num_blank: usize,
+    num_nonblank: usize,
+    num_paragraphs: usize,
+    num_sentences: usize,
+    num_comments: usize,
+    #[serde(skip)]
+    distribution: Distribution,
+}
+
+impl FileInfo {
+    pub fn new() -> Self {
+        FileInfo::default()
+    }
+
+    pub fn num_lines(&self) -> usize {
+        self.num_lines
+    }
+
+    pub fn num_words(&self) -> usize {
+        self.num_words
+    }
+
+    pub fn num_bytes(&self) -> usize {
+        self.num_bytes
+    }
+
+    pub fn num_chars(&self) -> usize {
+        self.num_chars
+    }
+
+    pub fn num_blank(&self) -> usize {
+        self.num_blank
+    }
+
+    pub fn num_nonblank(&self) -> usize {
+        self.num_nonblank
+    }
+
+    pub fn num_paragraphs(&self) -> usize {
+        self.num_paragraphs
+    }
+
+    pub fn num_sentences(&self) -> usize {
+        self.num_sentences
+    }
+
+    pub fn num_comments(&self) -> usize {
+        self.num_comments
+    }
+
+    pub fn distribution(&self) -> &Distribution {
+        &self.distribution
+    }
+}
+
+impl AddAssign<&FileInfo> for FileInfo {
+    fn add_assign(&mut self, other: &FileInfo) {
+        self.num_lines += other.num_lines;
+        self.num_words += other.num_words;
+        self.num_bytes += other.num_bytes;
+        self.num_chars += other.num_chars;
+        self.num_blank += other.num_blank;
+        self.num_nonblank += other.num_nonblank;
+        self.num_paragraphs += other.num_paragraphs;
+        self.num_sentences += other.num_sentences;
+        self.num_comments += other.num_comments;
+        self.distribution += &other.distribution;
+    }
+}
+
+impl AddAssign for FileInfo {
+    fn add_assign(&mut self, other: FileInfo) {
+        *self += &other;
+    }
+}
+
+impl Sum for FileInfo {
+    fn sum<I: Iterator<Item = FileInfo>>(iter: I) -> Self {
+        iter.fold(FileInfo::new(), |mut total, info| {
+            total += info;
+            total
+        })
+    }
+}
+
+impl<'a> Sum<&'a FileInfo> for FileInfo {
+    fn sum<I: Iterator<Item = &'a FileInfo>>(iter: I) -> Self {
+        iter.fold(FileInfo::new(), |mut total, info| {
+            total += info;
+            total
+        })
+    }
+}
+
+/// Selects which counters to compute and how, then counts inputs.
+///
+/// A new `Counter` computes everything with whitespace-separated words and
+/// no comment prefix. Counters that are switched off stay at zero in the
+/// resulting `FileInfo`.
+///
+/// Input can be pulled from a reader with `count`, or pushed in chunks of
+/// any size with `update` and closed with `finish`. Incomplete lines are
+/// buffered between chunks, unless nothing but lines and bytes is counted,
+/// so that a byte count of a stream without newlines takes no memory.
+///
+/// ```
+/// use std::io::Cursor;
+/// use wcr_v2::{Counter, Metric, UnicodeWords};
+///
+/// let mut counter = Counter::new()
+///     .chars(false)
+///     .metrics(&[Metric::Comments])
+///     .comment_prefix(Some("//"))
+///     .words_with(UnicodeWords);
+/// let info = counter.count(Cursor::new("// hi\nlet x = 1;\n")).unwrap();
+/// assert_eq!(info.num_lines(), 2);
+/// assert_eq!(info.num_words(), 4);
+/// assert_eq!(info.num_chars(), 0);
+/// assert_eq!(info.num_comments(), 1);
+///
+/// counter.update(b"// split ac");
+/// counter.update(b"ross chunks\n");
+/// assert_eq!(counter.finish().num_words(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Counter<W = Whitespace> {
+    splitter: W,
+    comment_prefix: Option<String>,
+    lines: bool,
+    words: bool,
+    bytes: bool,
+    chars: bool,
+    metrics: Vec<Metric>,
+    line_bucket: Option<usize>,
+    word_frequencies: bool,
+    stop_words: HashSet<String>,
+    info: FileInfo,
+    pending: Vec<u8>,
+    /// The length of an incomplete line that isn't kept in `pending`
+    pending_len: usize,
+    in_paragraph: bool,
+}
+
+impl Counter {
+    pub fn new() -> Self {
+        Counter {
+            splitter: Whitespace,
+            comment_prefix: None,
+            lines: true,
+            words: true,
+            bytes: true,
+            chars: true,
+            metrics: vec![
+                Metric::Blank,
+                Metric::NonBlank,
+                Metric::Paragraphs,
+                Metric::Sentences,
+                Metric::Comments,
+            ],
+            line_bucket: None,
+            word_frequencies: false,
+            stop_words: HashSet::new(),
+            info: FileInfo::new(),
+            pending: vec![],
+            pending_len: 0,
+            in_paragraph: false,
+        }
+    }
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Counter::new()
+    }
+}
+
+impl<W: WordSplitter> Counter<W> {
+    pub fn lines(mut self, enabled: bool) -> Self {
+        self.lines = enabled;
+        self
+    }
+
+    pub fn words(mut self, enabled: bool) -> Self {
+        self.words = enabled;
+        self
+    }
+
+    pub fn bytes(mut self, enabled: bool) -> Self {
+        self.bytes = enabled;
+        self
+    }
+
+    pub fn chars(mut self, enabled: bool) -> Self {
+        self.chars = enabled;
+        self
+    }
+
+    /// Replaces the enabled extra metrics.
+    pub fn metrics(mut self, metrics: &[Metric]) -> Self {
+        self.metrics = metrics.to_vec();
+        self
+    }
+
+    /// Sets the line comment prefix used for `Metric::Comments`.
+    pub fn comment_prefix(mut self, prefix: Option<&str>) -> Self {
+        self.comment_prefix = prefix.map(String::from);
+        self
+    }
+
+    /// Collects line lengths, without line endings, into buckets of
+    /// `bucket_size` characters.
+    pub fn line_histogram(mut self, bucket_size: Option<usize>) -> Self {
+        self.line_bucket = bucket_size.filter(|size| *size > 0);
+        self
+    }
+
+    /// Collects how often each word occurs. Words are lowercased first and
+    /// any in `stop_words` (given in lowercase) are left out.
+    pub fn word_frequencies(mut self, enabled: bool, stop_words: HashSet<String>) -> Self {
+        self.word_frequencies = enabled;
+        self.stop_words = stop_words;
+        self
+    }
+
+    /// Uses a different definition of a word.
+    pub fn words_with<S: WordSplitter>(self, splitter: S) -> Counter<S> {
+        Counter {
+            splitter,
+            comment_prefix: self.comment_prefix,
+            lines: self.lines,
+            words: self.words,
+            bytes: self.bytes,
+            chars: self.chars,
+            metrics: self.metrics,
+            line_bucket: self.line_bucket,
+            word_frequencies: self.word_frequencies,
+            stop_words: self.stop_words,
+            info: self.info,
+            pending: self.pending,
+            pending_len: self.pending_len,
+            in_paragraph: self.in_paragraph,
+        }
+    }
+
+    fn has(&self, metric: Metric) -> bool {
+        self.metrics.contains(&metric)
+    }
+
+    // Whether anything is counted that needs the text of each line
+    fn reads_lines(&self) -> bool {
+        self.words
+            || self.chars
+            || self.word_frequencies
+            || self.line_bucket.is_some()
+            || !self.metrics.is_empty()
+    }
+
+    /// Counts the next chunk of input.
+    pub fn update(&mut self, buf: &[u8]) {
+        let mut rest = buf;
+        if !self.reads_lines() {
+            while let Some(end) = rest.iter().position(|b| *b == b'\n') {
+                self.info.num_lines += 1;
+                self.info.num_bytes += self.pending_len + end + 1;
+                self.pending_len = 0;
+                rest = &rest[end + 1..];
+            }
+            self.pending_len += rest.len();
+            return;
+        }
+        while let Some(end) = rest.iter().position(|b| *b == b'\n') {
+            let (line, tail) = rest.split_at(end + 1);
+            if self.pending.is_empty() {
+                self.count_line(line);
+            } else {
+                let mut pending = std::mem::take(&mut self.pending);
+                pending.extend_from_slice(line);
+                self.count_line(&pending);
+                pending.clear();
+                self.pending = pending;
+            }
+            rest = tail;
+        }
+        self.pending.extend_from_slice(rest);
+    }
+
+    /// The counts of the complete lines seen so far, before any counters
+    /// that are switched off are cleared.
+    pub fn partial(&self) -> &FileInfo {
+        &self.info
+    }
+
+    /// All bytes given to `update` so far, including an incomplete line.
+    pub fn bytes_seen(&self) -> usize {
+        self.info.num_bytes + self.pending.len() + self.pending_len
+    }
+
+    /// Counts any final line without a newline and returns the result,
+    /// leaving the counter ready for the next input.
+    pub fn finish(&mut self) -> FileInfo {
+        if !self.pending.is_empty() {
+            let pending = std::mem::take(&mut self.pending);
+            self.count_line(&pending);
+        }
+        if self.pending_len > 0 {
+            self.info.num_lines += 1;
+            self.info.num_bytes += std::mem::take(&mut self.pending_len);
+        }
+        self.in_paragraph = false;
+        let mut info = std::mem::take(&mut self.info);
+        info.num_nonblank = info.num_lines - info.num_blank;
+
+        if !self.lines {
+            info.num_lines = 0;
+        }
+        if !self.bytes {
+            info.num_bytes = 0;
+        }
+        if !self.has(Metric::Blank) {
+            info.num_blank = 0;
+        }
+        if !self.has(Metric::NonBlank) {
+            info.num_nonblank = 0;
+        }
+        if !self.has(Metric::Paragraphs) {
+            info.num_paragraphs = 0;
+        }
+        info
+    }
+
+    /// Counts everything `file` yields up to EOF.
+    pub fn count(&mut self, file: impl BufRead) -> Result<FileInfo, WcError> {
+        self.count_with(file, |_| {})
+    }
+
+    /// Like `count`, but calls `on_chunk` with the counter after every
+    /// chunk read, e.g. to report progress.
+    pub fn count_with(
+        &mut self,
+        mut file: impl BufRead,
+        mut on_chunk: impl FnMut(&Self),
+    ) -> Result<FileInfo, WcError> {
+        loop {
+            let buf = match file.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.pending.clear();
+                    self.pending_len = 0;
+                    self.info = FileInfo::new();
+                    return Err(e.into());
+                }
+            };
+            if buf.is_empty() {
+                break; // EOF
+            }
+            let len = buf.len();
+            self.update(buf);
+            file.consume(len);
+            on_chunk(self);
+        }
+        Ok(self.finish())
+    }
+
+    fn count_line(&mut self, bytes: &[u8]) {
+        let line = String::from_utf8_lossy(bytes);
+        self.info.num_bytes += bytes.len();
+        self.info.num_lines += 1;
+        if self.word_frequencies {
+            let Counter { splitter, stop_words, info, .. } = self;
+            let mut num_words = 0;
+            for word in splitter.words(&line) {
+                num_words += 1;
+                let word = word.to_lowercase();
+                if !stop_words.contains(&word) {
+                    info.distribution.add_word(word);
+                }
+            }
+            if self.words {
+                self.info.num_words += num_words;
+            }
+        } else if self.words {
+            self.info.num_words += self.splitter.count_words(&line);
+        }
+        if self.chars {
+            self.info.num_chars += line.chars().count();
+        }
+        if self.has(Metric::Sentences) {
+            self.info.num_sentences += metrics::count_sentences(&line);
+        }
+        if let Some(bucket_size) = self.line_bucket {
+            let len = line.trim_end_matches(['\n', '\r']).chars().count();
+            self.info.distribution.add_line_length(bucket_size, len);
+        }
+
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            self.info.num_blank += 1;
+            self.in_paragraph = false;
+        } else {
+            if !self.in_paragraph {
+                self.info.num_paragraphs += 1;
+                self.in_paragraph = true;
+            }
+            let is_comment = self
+                .comment_prefix
+                .as_deref()
+                .is_some_and(|prefix| trimmed.starts_with(prefix));
+            if is_comment && self.has(Metric::Comments) {
+                self.info.num_comments += 1;
+            }
+        }
+    }
+}
+
+impl<W: WordSplitter> Write for Counter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Counts everything in `file` with the given word definition and comment
+/// prefix; a shorthand for the equivalent `Counter`.
+pub fn count(
+    file: impl BufRead,
+    splitter: &impl WordSplitter,
+    comment_prefix: Option<&str>,
+) -> Result<FileInfo, WcError> {
+    Counter::new()
+        .comment_prefix(comment_prefix)
+        .words_with(splitter)
+        .count(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::UnicodeWords;
+
+    #[test]
+    fn test_count() {
+        let text = "I don't want the world, I just want your half.\r\n";
+        let info = count(Cursor::new(text), &Whitespace, None);
+        assert!(info.is_ok());
+        let expected = FileInfo {
+            num_lines: 1,
+            num_words: 10, // "I", "don't", "want", "the", "world,", "I", "just", "want", "your", "half."
+            num_bytes: text.len(),
+            num_chars: text.chars().count(),
+            num_blank: 0,
+            num_nonblank: 1,
+            num_paragraphs: 1,
+            num_sentences: 1,
+            num_comments: 0,
+            distribution: Distribution::default(),
+        };
+        assert_eq!(info.unwrap(), expected);
+
+        let info = count(Cursor::new("日本語のテキスト\n"), &UnicodeWords, None).unwrap();
+        assert_eq!(info.num_words, 5); // "日", "本", "語", "の", "テキスト"
+    }
+
+    #[test]
+    fn test_count_metrics() {
+        let text = "// Adds one.\nfn inc(x: u8) -> u8 {\n\n    // Might overflow!\n    x + 1\n}\n\n  \n";
+        let info = count(Cursor::new(text), &Whitespace, Some("//")).unwrap();
+        assert_eq!(info.num_lines, 8);
+        assert_eq!(info.num_blank, 3);
+        assert_eq!(info.num_nonblank, 5);
+        assert_eq!(info.num_paragraphs, 2);
+        assert_eq!(info.num_sentences, 2);
+        assert_eq!(info.num_comments, 2);
+
+        let info = count(Cursor::new(text), &Whitespace, None).unwrap();
+        assert_eq!(info.num_comments, 0);
+    }
+
+    #[test]
+    fn test_counter_selection() {
+        let text = "one two\n\nthree.\n";
+        let info = Counter::new()
+            .words(false)
+            .bytes(false)
+            .metrics(&[Metric::Blank])
+            .count(Cursor::new(text))
+            .unwrap();
+        assert_eq!(info.num_lines, 3);
+        assert_eq!(info.num_words, 0);
+        assert_eq!(info.num_bytes, 0);
+        assert_eq!(info.num_chars, text.len());
+        assert_eq!(info.num_blank, 1);
+        assert_eq!(info.num_nonblank, 0);
+        assert_eq!(info.num_sentences, 0);
+    }
+
+    #[test]
+    fn test_distribution() {
+        let stop_words = HashSet::from(["the".to_string()]);
+        let info = Counter::new()
+            .line_histogram(Some(10))
+            .word_frequencies(true, stop_words)
+            .count(Cursor::new("The cat saw the dog\r\nA Cat\n"))
+            .unwrap();
+        assert_eq!(info.num_words, 7);
+        let buckets: Vec<_> = info.distribution.buckets().iter().map(|b| (b.from, b.lines)).collect();
+        assert_eq!(buckets, vec![(0, 1), (10, 1)]);
+        let top = &info.distribution.top_words(1)[0];
+        assert_eq!((top.word.as_str(), top.count), ("cat", 2));
+    }
+
+    #[test]
+    fn test_update_in_chunks() {
+        let text = "// Adds one.\nfn inc(x: u8) -> u8 {\n\n    x + 1\n}\nlast line, no newline";
+        let expected = count(Cursor::new(text), &Whitespace, Some("//")).unwrap();
+        for size in [1, 2, 5, 64] {
+            let mut counter = Counter::new().comment_prefix(Some("//"));
+            for chunk in text.as_bytes().chunks(size) {
+                counter.update(chunk);
+            }
+            assert_eq!(counter.finish(), expected, "chunk size {}", size);
+        }
+    }
+
+    #[test]
+    fn test_lines_and_bytes_not_buffered() {
+        let mut counter = Counter::new().words(false).chars(false).metrics(&[]);
+        let chunk = [b'x'; 4096];
+        for _ in 0..256 {
+            counter.update(&chunk);
+        }
+        assert!(counter.pending.is_empty());
+        assert_eq!(counter.bytes_seen(), 1 << 20);
+        assert_eq!(counter.partial().num_lines(), 0);
+        counter.update(b"y\nz");
+        assert_eq!(counter.partial().num_bytes(), (1 << 20) + 2);
+        let info = counter.finish();
+        assert_eq!((info.num_lines, info.num_bytes), (2, (1 << 20) + 3));
+
+        // The same as when the lines are read
+        let text = "one\ntwo three\nno newline";
+        let expected = Counter::new().count(Cursor::new(text)).unwrap();
+        for size in [1, 3, 64] {
+            let mut counter = Counter::new().words(false).chars(false).metrics(&[]);
+            for chunk in text.as_bytes().chunks(size) {
+                counter.update(chunk);
+            }
+            let info = counter.finish();
+            assert_eq!(info.num_lines, expected.num_lines, "chunk size {}", size);
+            assert_eq!(info.num_bytes, expected.num_bytes, "chunk size {}", size);
+        }
+    }
+
+    #[test]
+    fn test_count_invalid_utf8() {
+        let info = count(Cursor::new(b"caf\xe9 ol\xe9\n"), &Whitespace, None).unwrap();
+        assert_eq!(info.num_lines, 1);
+        assert_eq!(info.num_words, 2);
+        assert_eq!(info.num_bytes, 9);
+    }
+
+    #[test]
+    fn test_add_and_sum() {
+        let a = count(Cursor::new("a b\n"), &Whitespace, None).unwrap();
+        let b = count(Cursor::new("c\n\n"), &Whitespace, None).unwrap();
+
+        let mut total = a.clone();
+        total += &b;
+        assert_eq!(total.num_lines(), 3);
+        assert_eq!(total.num_words(), 3);
+        assert_eq!(total.num_blank(), 1);
+
+        assert_eq!([&a, &b].into_iter().sum::<FileInfo>(), total);
+        assert_eq!(vec![a, b].into_iter().sum::<FileInfo>(), total);
+    }
+}
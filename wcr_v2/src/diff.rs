@@ -0,0 +1,199 @@
+use serde::Serialize;
+use std::process::Command;
+
+use crate::{Counts, WcError, report::escape_field};
+
+/// Where `--diff` takes the old version of each file from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffSource {
+    /// The two file operands are OLD and NEW.
+    Files,
+    /// Each file operand is compared with its contents at this git revision.
+    Git(String),
+}
+
+/// How one counter changed between the old and the new version.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Change {
+    pub counter: &'static str,
+    pub old: usize,
+    pub new: usize,
+    pub delta: i64,
+    /// `None` when the old count is zero.
+    pub percent: Option<f64>,
+}
+
+/// The selected counters of two versions of a file, side by side.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Diff {
+    pub old: String,
+    pub new: String,
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    pub fn new(old: &str, old_counts: &Counts, new: &str, new_counts: &Counts) -> Self {
+        let changes = old_counts
+            .fields()
+            .into_iter()
+            .zip(new_counts.fields())
+            .map(|((counter, old), (_, new))| Change {
+                counter,
+                old,
+                new,
+                delta: new as i64 - old as i64,
+                percent: (old != 0).then(|| (new as f64 - old as f64) * 100.0 / old as f64),
+            })
+            .collect();
+        Diff {
+            old: old.to_string(),
+            new: new.to_string(),
+            changes,
+        }
+    }
+
+    /// A `---`/`+++` header followed by one aligned row per counter.
+    pub fn to_text(&self) -> String {
+        let mut rows = vec![[
+            "".to_string(),
+            "old".into(),
+            "new".into(),
+            "delta".into(),
+            "change".into(),
+        ]];
+        for change in &self.changes {
+            rows.push([
+                change.counter.to_string(),
+                change.old.to_string(),
+                change.new.to_string(),
+                format!("{:+}", change.delta),
+                format_percent(change.percent),
+            ]);
+        }
+        let mut widths = [0; 5];
+        for row in &rows {
+            for (width, field) in widths.iter_mut().zip(row) {
+                *width = (*width).max(field.len());
+            }
+        }
+
+        let mut out = format!("--- {}\n+++ {}\n", self.old, self.new);
+        for row in &rows {
+            let mut line = format!("{:<w$}", row[0], w = widths[0]);
+            for (field, width) in row.iter().zip(widths).skip(1) {
+                line += &format!("  {:>w$}", field, w = width);
+            }
+            out += line.trim_end();
+            out += "\n";
+        }
+        out
+    }
+}
+
+fn format_percent(percent: Option<f64>) -> String {
+    match percent {
+        Some(percent) => format!("{:+.1}%", percent),
+        None => "n/a".to_string(),
+    }
+}
+
+/// Renders a header and one row per counter of every diff.
+pub fn to_delimited(diffs: &[Diff], delimiter: char) -> String {
+    let header = [
+        "old_name", "new_name", "counter", "old", "new", "delta", "percent",
+    ];
+    let mut rows = vec![header.join(&delimiter.to_string())];
+    for diff in diffs {
+        for change in &diff.changes {
+            let row = [
+                escape_field(&diff.old, delimiter),
+                escape_field(&diff.new, delimiter),
+                change.counter.to_string(),
+                change.old.to_string(),
+                change.new.to_string(),
+                change.delta.to_string(),
+                change
+                    .percent
+                    .map_or_else(String::new, |p| format!("{:.1}", p)),
+            ];
+            rows.push(row.join(&delimiter.to_string()));
+        }
+    }
+    rows.iter().map(|row| format!("{}\n", row)).collect()
+}
+
+/// The contents of `filename` at `rev`, from `git show`. The path is taken
+/// relative to the current directory rather than to the top of the work tree.
+pub fn git_show(rev: &str, filename: &str) -> Result<Vec<u8>, WcError> {
+    let spec = format!("{}:./{}", rev, filename.trim_start_matches("./"));
+    let output = Command::new("git").args(["show", &spec]).output()?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(WcError::Git {
+            spec,
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff() -> Diff {
+        Diff::new(
+            "old.txt",
+            &Counts {
+                lines: Some(4),
+                words: Some(0),
+                bytes: Some(100),
+                ..Counts::default()
+            },
+            "new.txt",
+            &Counts {
+                lines: Some(3),
+                words: Some(2),
+                bytes: Some(150),
+                ..Counts::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_changes() {
+        let changes = diff().changes;
+        assert_eq!(changes[0].delta, -1);
+        assert_eq!(changes[0].percent, Some(-25.0));
+        assert_eq!(changes[1].percent, None);
+        assert_eq!(changes[2].percent, Some(50.0));
+    }
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!(
+            diff().to_text(),
+            concat!(
+                "--- old.txt\n",
+                "+++ new.txt\n",
+                "       old  new  delta  change\n",
+                "lines    4    3     -1  -25.0%\n",
+                "words    0    2     +2     n/a\n",
+                "bytes  100  150    +50  +50.0%\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_to_delimited() {
+        assert_eq!(
+            to_delimited(&[diff()], ','),
+            concat!(
+                "old_name,new_name,counter,old,new,delta,percent\n",
+                "old.txt,new.txt,lines,4,3,-1,-25.0\n",
+                "old.txt,new.txt,words,0,2,2,\n",
+                "old.txt,new.txt,bytes,100,150,50,50.0\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use serde::Serialize;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    ops::AddAssign,
+};
+
+/// Line lengths and word frequencies, collected when a `Counter` is asked
+/// for them with `line_histogram` or `word_frequencies`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Distribution {
+    bucket_size: usize,
+    line_lengths: BTreeMap<usize, usize>,
+    words: HashMap<String, usize>,
+}
+
+/// A line-length bucket covering `from..=to` characters.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bucket {
+    pub from: usize,
+    pub to: usize,
+    pub lines: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WordCount {
+    pub word: String,
+    pub count: usize,
+}
+
+impl Distribution {
+    pub(crate) fn add_line_length(&mut self, bucket_size: usize, len: usize) {
+        self.bucket_size = bucket_size;
+        *self.line_lengths.entry(len / bucket_size).or_default() += 1;
+    }
+
+    pub(crate) fn add_word(&mut self, word: String) {
+        *self.words.entry(word).or_default() += 1;
+    }
+
+    /// The non-empty line-length buckets, shortest first.
+    pub fn buckets(&self) -> Vec<Bucket> {
+        self.line_lengths
+            .iter()
+            .map(|(bucket, lines)| Bucket {
+                from: bucket * self.bucket_size,
+                to: (bucket + 1) * self.bucket_size - 1,
+                lines: *lines,
+            })
+            .collect()
+    }
+
+    /// The `n` most frequent words, ties broken alphabetically.
+    pub fn top_words(&self, n: usize) -> Vec<WordCount> {
+        let mut words: Vec<_> = self.words.iter().collect();
+        words.sort_by_key(|(word, count)| (Reverse(**count), *word));
+        words
+            .into_iter()
+            .take(n)
+            .map(|(word, count)| WordCount {
+                word: word.clone(),
+                count: *count,
+            })
+            .collect()
+    }
+}
+
+impl AddAssign<&Distribution> for Distribution {
+    fn add_assign(&mut self, other: &Distribution) {
+        if other.bucket_size != 0 {
+            self.bucket_size = other.bucket_size;
+        }
+        for (bucket, lines) in &other.line_lengths {
+            *self.line_lengths.entry(*bucket).or_default() += lines;
+        }
+        for (word, count) in &other.words {
+            *self.words.entry(word.clone()).or_default() += count;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets() {
+        let mut dist = Distribution::default();
+        for len in [0, 3, 10, 12, 19, 45] {
+            dist.add_line_length(10, len);
+        }
+        assert_eq!(
+            dist.buckets(),
+            vec![
+                Bucket { from: 0, to: 9, lines: 2 },
+                Bucket { from: 10, to: 19, lines: 3 },
+                Bucket { from: 40, to: 49, lines: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_top_words() {
+        let mut dist = Distribution::default();
+        for word in ["b", "a", "c", "b", "a", "b"] {
+            dist.add_word(word.to_string());
+        }
+        let top: Vec<_> = dist
+            .top_words(2)
+            .into_iter()
+            .map(|w| (w.word, w.count))
+            .collect();
+        assert_eq!(top, vec![("b".to_string(), 3), ("a".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_add_assign() {
+        let mut a = Distribution::default();
+        a.add_line_length(5, 7);
+        a.add_word("x".to_string());
+        let mut b = Distribution::default();
+        b.add_line_length(5, 8);
+        b.add_word("x".to_string());
+
+        let mut total = Distribution::default();
+        total += &a;
+        total += &b;
+        assert_eq!(total.buckets(), vec![Bucket { from: 5, to: 9, lines: 2 }]);
+        assert_eq!(total.top_words(1)[0].count, 2);
+    }
+}
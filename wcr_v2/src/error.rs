@@ -0,0 +1,64 @@
+use std::{fmt, io};
+
+/// Everything that can go wrong while parsing arguments or counting.
+#[derive(Debug)]
+pub enum WcError {
+    /// Reading or opening an input failed.
+    Io(io::Error),
+    /// The `--files0-from` list could not be opened.
+    FilesFrom { list: String, source: io::Error },
+    /// The command line is invalid.
+    Usage(String),
+    /// An `--include` or `--exclude` pattern is invalid.
+    Glob(globset::Error),
+    /// The report could not be serialized.
+    Json(serde_json::Error),
+    /// `git show` could not produce an old version for `--git`.
+    Git { spec: String, message: String },
+}
+
+impl fmt::Display for WcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WcError::Io(e) => write!(f, "{}", e),
+            WcError::FilesFrom { list, source } => {
+                write!(f, "cannot open '{}' for reading: {}", list, source)
+            }
+            WcError::Usage(msg) => write!(f, "{}", msg),
+            WcError::Glob(e) => write!(f, "{}", e),
+            WcError::Json(e) => write!(f, "{}", e),
+            WcError::Git { spec, message } => write!(f, "git show {}: {}", spec, message),
+        }
+    }
+}
+
+impl std::error::Error for WcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WcError::Io(e) => Some(e),
+            WcError::FilesFrom { source, .. } => Some(source),
+            WcError::Usage(_) => None,
+            WcError::Glob(e) => Some(e),
+            WcError::Json(e) => Some(e),
+            WcError::Git { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for WcError {
+    fn from(e: io::Error) -> Self {
+        WcError::Io(e)
+    }
+}
+
+impl From<globset::Error> for WcError {
+    fn from(e: globset::Error) -> Self {
+        WcError::Glob(e)
+    }
+}
+
+impl From<serde_json::Error> for WcError {
+    fn from(e: serde_json::Error) -> Self {
+        WcError::Json(e)
+    }
+}
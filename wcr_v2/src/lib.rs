@@ -1,7 +1,34 @@
-use clap::{App, Arg};
-use std::{error::Error, fs::File, io::{self, BufRead, BufReader}};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::{Generator, Shell};
+use progress::Progress;
+use std::{collections::{BTreeMap, HashSet}, fs::{self, File}, io::{self, BufRead, BufReader, Write}, str::FromStr};
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+mod cache;
+mod counter;
+mod diff;
+mod distribution;
+mod error;
+mod metrics;
+mod progress;
+mod report;
+mod stream;
+mod walk;
+mod words;
+
+pub use cache::{Cache, CacheCommand};
+pub use counter::{count, Counter, FileInfo};
+pub use diff::{Change, Diff, DiffSource};
+pub use distribution::{Bucket, Distribution, WordCount};
+pub use error::WcError;
+pub use metrics::{comment_prefix, Metric};
+pub use report::{Counts, Entry, Format, Group, GroupBy, Report, Selection};
+pub use stream::CountingWriter;
+#[cfg(feature = "tokio")]
+pub use stream::CountingReader;
+pub use walk::WalkOptions;
+pub use words::{RegexWords, SeparatorWords, UnicodeWords, Whitespace, WordMode, WordSplitter};
+
+type MyResult<T> = Result<T, WcError>;
 
 #[derive(Debug)]
 pub struct Config {
@@ -10,101 +37,680 @@ pub struct Config {
     words: bool,
     bytes: bool,
     chars: bool,
+    files0_from: Option<String>,
+    format: Format,
+    total: TotalMode,
+    word_mode: WordMode,
+    metrics: Vec<Metric>,
+    recursive: bool,
+    walk: WalkOptions,
+    group_by: Option<GroupBy>,
+    histogram: Option<usize>,
+    top_words: Option<usize>,
+    stop_words: HashSet<String>,
+    progress: bool,
+    diff: Option<DiffSource>,
+    cache: Option<String>,
+    cache_command: Option<CacheCommand>,
+    verbose: bool,
+    completions: Option<Completions>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum TotalMode {
+    Auto,
+    Always,
+    Only,
+    Never,
+}
+
+impl Config {
+    fn selection(&self) -> Selection {
+        Selection {
+            lines: self.lines,
+            words: self.words,
+            bytes: self.bytes,
+            chars: self.chars,
+            metrics: self.metrics.clone(),
+            line_lengths: self.histogram.is_some(),
+            top_words: self.top_words,
+        }
+    }
+
+    fn num_fields(&self) -> usize {
+        Counts::new(&FileInfo::new(), &self.selection()).fields().len()
+    }
+
+    fn counter(&self, filename: &str) -> Counter<&WordMode> {
+        Counter::new()
+            .lines(self.lines)
+            .words(self.words)
+            .bytes(self.bytes)
+            .chars(self.chars)
+            .metrics(&self.metrics)
+            .comment_prefix(comment_prefix(filename))
+            .line_histogram(self.histogram)
+            .word_frequencies(self.top_words.is_some(), self.stop_words.clone())
+            .words_with(&self.word_mode)
+    }
+
+    /// Everything besides the file itself that decides what `counter`
+    /// produces, or None when the counts can't be cached because a
+    /// histogram or word frequencies were asked for.
+    fn cache_flags(&self, filename: &str) -> Option<String> {
+        if self.histogram.is_some() || self.top_words.is_some() {
+            return None;
+        }
+        Some(format!(
+            "{} {:?} {:?} {:?} {:?}",
+            env!("CARGO_PKG_VERSION"),
+            [self.lines, self.words, self.bytes, self.chars],
+            self.word_mode,
+            self.metrics,
+            comment_prefix(filename),
+        ))
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    version = "0.1.0",
+    author = "Your Name <yourmail@example.com>",
+    about = "rust version of wc",
+    disable_help_subcommand = true,
+)]
+struct Args {
+    #[arg(value_name = "FILE", help = "File(s) to input [default: -]")]
+    files: Vec<String>,
+
+    #[arg(short = 'l', long, help = "Count lines")]
+    lines: bool,
+
+    #[arg(short = 'w', long, help = "Count words")]
+    words: bool,
+
+    #[arg(short = 'c', long, help = "Count bytes", conflicts_with = "chars")]
+    bytes: bool,
+
+    #[arg(short = 'm', long, help = "Count characters")]
+    chars: bool,
+
+    #[arg(
+        long,
+        value_name = "F",
+        help = "Read input from the files specified by NUL-terminated names in file F; if F is - then read names from standard input"
+    )]
+    files0_from: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format",
+        value_enum,
+        default_value_t = Format::Text
+    )]
+    format: Format,
+
+    #[arg(
+        long,
+        value_name = "WHEN",
+        help = "When to print a line with total counts",
+        value_enum,
+        default_value_t = TotalMode::Auto
+    )]
+    total: TotalMode,
+
+    #[arg(
+        long,
+        value_name = "MODE",
+        help = "What counts as a word: whitespace, unicode, regex:<pattern> or sep:<chars>",
+        value_parser = WordMode::from_str,
+        default_value = "whitespace"
+    )]
+    word_mode: WordMode,
+
+    #[arg(
+        long,
+        value_name = "METRIC",
+        help = "Extra counters to print, separated by commas",
+        value_enum,
+        value_delimiter = ','
+    )]
+    metrics: Vec<Metric>,
+
+    #[arg(short = 'r', long, help = "Count the files below directories, honouring .gitignore")]
+    recursive: bool,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "With -r, only count files matching GLOB",
+        requires = "recursive"
+    )]
+    include: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "With -r, skip files and directories matching GLOB",
+        requires = "recursive"
+    )]
+    exclude: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "KEY",
+        help = "Print subtotals per file extension or directory",
+        value_enum
+    )]
+    group_by: Option<GroupBy>,
+
+    #[arg(
+        long,
+        value_name = "KIND",
+        help = "Print the distribution of line lengths",
+        value_enum
+    )]
+    histogram: Option<Histogram>,
+
+    #[arg(
+        long,
+        value_name = "CHARS",
+        help = "Width of the line-length buckets",
+        value_parser = clap::value_parser!(u64).range(1..),
+        default_value = "10",
+        requires = "histogram"
+    )]
+    bucket_size: u64,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Print the N most frequent words, ignoring case",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    top_words: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Leave the words listed in FILE, one per line, out of --top-words",
+        requires = "top_words"
+    )]
+    stop_words: Option<String>,
+
+    #[arg(long, help = "Show the counts so far on stderr while counting")]
+    progress: bool,
+
+    #[arg(
+        long,
+        help = "Compare the counts of OLD and NEW, given as the two files",
+        conflicts_with_all = ["files0_from", "recursive", "group_by", "histogram", "top_words"]
+    )]
+    diff: bool,
+
+    #[arg(
+        long,
+        value_name = "REV",
+        help = "Compare each file with its contents at git revision REV",
+        conflicts_with_all = ["files0_from", "recursive", "group_by", "histogram", "top_words"]
+    )]
+    git: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Reuse the counts of files that have not changed since an earlier run, stored in DIR"
+    )]
+    cache: Option<String>,
+
+    #[arg(
+        long,
+        help = "Remove every entry from the cache and exit",
+        requires = "cache",
+        conflicts_with_all = ["cache_invalidate", "cache_gc"]
+    )]
+    cache_clear: bool,
+
+    #[arg(
+        long,
+        help = "Remove the cache entries of the given files and exit",
+        requires = "cache",
+        conflicts_with = "cache_gc"
+    )]
+    cache_invalidate: bool,
+
+    #[arg(
+        long,
+        help = "Remove the cache entries of files that changed or are gone and exit",
+        requires = "cache"
+    )]
+    cache_gc: bool,
+
+    #[arg(short = 'v', long, help = "Report cache hits and cache maintenance on stderr")]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Subcommands share the first operand with file names, so `completions`
+/// is reserved: a file of that name has to be counted as `-- completions`.
+/// The `help` subcommand clap would add is turned off, so `help` is a file.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print a shell completion script or the man page
+    #[command(hide = true)]
+    Completions {
+        #[arg(value_enum)]
+        target: Completions,
+    },
+}
+
+/// What `--histogram` shows the distribution of.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum Histogram {
+    Lines,
+}
+
+/// What the hidden `completions` subcommand prints.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Completions {
+    Bash,
+    Zsh,
+    Fish,
+    Man,
 }
 
 pub fn get_args() -> MyResult<Config> {
-    let matches = App::new("wcr_v2")
-        .version("0.1.0")
-        .author("Your Name <yourmail@example.com>")
-        .about("rust version of wc with clap 2")
-        .arg(
-            Arg::with_name("files")
-                .help("File(s) to input")
-                .value_name("FILE")
-                .default_value("-")
-                .multiple(true)
-        )
-        .arg(
-            Arg::with_name("lines")
-                .short("l")
-                .long("lines")
-                .help("Count lines")
-                .takes_value(false) 
-        )
-        .arg(
-            Arg::with_name("words")
-                .short("w")
-                .long("words")
-                .help("Count words")
-                .takes_value(false)
-        )
-        .arg(
-            Arg::with_name("bytes")
-                .short("c")
-                .long("bytes")
-                .help("Count bytes")
-                .takes_value(false)
-                .conflicts_with("chars")
-        )
-        .arg(
-            Arg::with_name("chars")
-                .short("m")
-                .long("chars")
-                .help("Count characters")
-                .takes_value(false)
-                .conflicts_with("bytes")
-        )
-        .get_matches();
-    let mut lines = matches.is_present("lines");
-    let mut words = matches.is_present("words");
-    let mut bytes = matches.is_present("bytes");
-    let mut chars = matches.is_present("chars");
+    let args = Args::parse();
+    let (mut lines, mut words, mut bytes, mut chars) = (args.lines, args.words, args.bytes, args.chars);
     if [lines, words, bytes, chars].iter().all(|v| !v) {
         lines = true;
         words = true;
         bytes = true;
         chars = false;
     }
+    if args.files0_from.is_some() && !args.files.is_empty() {
+        return Err(WcError::Usage(format!(
+            "extra operand '{}'\nfile operands cannot be combined with --files0-from",
+            args.files[0]
+        )));
+    }
+    let format = args.format;
+    let histogram = args.histogram.map(|Histogram::Lines| args.bucket_size as usize);
+    let top_words = args.top_words.map(|n| n as usize);
+    if (histogram.is_some() || top_words.is_some()) && matches!(format, Format::Csv | Format::Tsv) {
+        return Err(WcError::Usage(
+            "--histogram and --top-words can't be used with --format csv or tsv".to_string(),
+        ));
+    }
+    let diff = match (args.git, args.diff) {
+        (Some(rev), _) if args.files.is_empty() => {
+            return Err(WcError::Usage(format!("--git {} needs at least one file", rev)));
+        }
+        (Some(rev), _) => Some(DiffSource::Git(rev)),
+        (None, true) if args.files.len() != 2 => {
+            return Err(WcError::Usage("--diff needs exactly two files, OLD and NEW".to_string()));
+        }
+        (None, true) => Some(DiffSource::Files),
+        (None, false) => None,
+    };
+    let stop_words = match &args.stop_words {
+        Some(filename) => read_stop_words(filename)?,
+        None => HashSet::new(),
+    };
     Ok(Config {
-        files: matches.values_of_lossy("files").unwrap(),
+        files: if args.files.is_empty() { vec!["-".to_string()] } else { args.files },
         lines,
         words,
         bytes,
         chars,
+        files0_from: args.files0_from,
+        format,
+        total: args.total,
+        word_mode: args.word_mode,
+        metrics: args.metrics,
+        recursive: args.recursive,
+        walk: WalkOptions::new(&args.include, &args.exclude)?,
+        group_by: args.group_by,
+        histogram,
+        top_words,
+        stop_words,
+        progress: args.progress,
+        diff,
+        cache: args.cache,
+        cache_command: if args.cache_clear {
+            Some(CacheCommand::Clear)
+        } else if args.cache_invalidate {
+            Some(CacheCommand::Invalidate)
+        } else if args.cache_gc {
+            Some(CacheCommand::Gc)
+        } else {
+            None
+        },
+        verbose: args.verbose,
+        completions: args.command.map(|Command::Completions { target }| target),
     })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
+fn read_stop_words(filename: &str) -> MyResult<HashSet<String>> {
+    let mut stop_words = HashSet::new();
+    for line in open(filename).map_err(|e| named(filename, e))?.lines() {
+        let word = line.map_err(|e| named(filename, e.into()))?.trim().to_lowercase();
+        if !word.is_empty() {
+            stop_words.insert(word);
+        }
+    }
+    Ok(stop_words)
+}
+
+/// Counts, or compares, the configured inputs and prints the report.
+/// Returns false if any input could not be counted.
+pub fn run(config: Config) -> MyResult<bool> {
+    if let Some(target) = config.completions {
+        print_completions(target)?;
+        return Ok(true);
+    }
+    if let Some(source) = &config.diff {
+        run_diff(&config, source)?;
+        return Ok(true);
+    }
+    // Answers SIGUSR1 from here on, so a signal sent while the file list
+    // is read or a tree is walked doesn't end the program
+    let mut progress = Progress::new(config.progress);
+    let mut all_counted = true;
+    let mut files = match &config.files0_from {
+        Some(list) => {
+            // Checked before any directory is expanded, so the positions
+            // refer to the list
+            let mut names = vec![];
+            for (i, filename) in read_files0_from(list)?.into_iter().enumerate() {
+                if filename.is_empty() {
+                    eprintln!("{}:{}: invalid zero-length file name", list, i + 1);
+                    all_counted = false;
+                } else if list == "-" && filename == "-" {
+                    eprintln!("when reading file names from standard input, no file name of '-' allowed");
+                    all_counted = false;
+                } else {
+                    names.push(filename);
+                }
+            }
+            names
+        }
+        None => config.files.clone(),
+    };
+    if config.recursive {
+        let all_read;
+        (files, all_read) = walk::expand(&files, &config.walk);
+        all_counted &= all_read;
+    }
+    let mut cache = config
+        .cache
+        .as_deref()
+        .map(|dir| Cache::open(dir).map_err(|e| named(dir, e.into())))
+        .transpose()?;
+    if let (Some(cache), Some(command)) = (&cache, config.cache_command) {
+        let removed = match command {
+            CacheCommand::Clear => cache.clear()?,
+            CacheCommand::Invalidate => cache.invalidate(&files)?,
+            CacheCommand::Gc => cache.gc()?,
+        };
+        if config.verbose {
+            eprintln!("{}: removed {} cache entries", cache.dir().display(), removed);
+        }
+        return Ok(true);
+    }
     let mut total = FileInfo::new();
-    for filename in &config.files {
-        match open(filename) {
-            Err(e) => eprintln!("{}: {}", filename, e),
-            Ok(file) => {
-                let file_info = count(file)?;
-                println!(
-                    "{}{}{}{}{}",
-                    format_field(file_info.num_lines, config.lines),
-                    format_field(file_info.num_words, config.words),
-                    format_field(file_info.num_bytes, config.bytes),
-                    format_field(file_info.num_chars, config.chars),
-                    if filename == "-" { String::new() } else { format!(" {}", filename) }
-                );
-                total.add(&file_info);
+    let mut groups: BTreeMap<String, FileInfo> = BTreeMap::new();
+    let mut report = Report::default();
+    let mut num_counted = 0;
+    let selection = config.selection();
+    for filename in &files {
+        let file = match open(filename) {
+            Ok(file) => file,
+            Err(e) => {
+                report_failure(&mut report, filename, &e);
+                all_counted = false;
+                continue;
+            }
+        };
+        let flags = cache.as_ref().and_then(|_| config.cache_flags(filename));
+        let cached = match (&mut cache, &flags) {
+            (Some(cache), Some(flags)) => cache.get(filename, flags),
+            _ => None,
+        };
+        let file_info = match cached {
+            Some(file_info) => {
+                if config.verbose {
+                    eprintln!("{}: cache hit", filename);
+                }
+                file_info
+            }
+            None => {
+                progress.start(filename);
+                let result = config
+                    .counter(filename)
+                    .count_with(file, |counter| progress.update(counter));
+                progress.finish();
+                let file_info = match result {
+                    Ok(file_info) => file_info,
+                    Err(e) => {
+                        report_failure(&mut report, filename, &e);
+                        all_counted = false;
+                        continue;
+                    }
+                };
+                if let (Some(cache), Some(flags)) = (&cache, &flags)
+                    && let Err(e) = cache.put(filename, flags, &file_info)
+                {
+                    eprintln!("{}: {}", cache.dir().display(), e);
+                }
+                file_info
             }
+        };
+        total += &file_info;
+        num_counted += 1;
+        if let Some(group_by) = config.group_by {
+            *groups.entry(group_by.key(filename)).or_default() += &file_info;
+        }
+        if config.total != TotalMode::Only {
+            report.files.push(Entry::Counted {
+                name: filename.to_string(),
+                counts: Counts::new(&file_info, &selection),
+            });
         }
     }
-    if config.files.len() > 1 {
-        println!(
-            "{}{}{}{} total",
-            format_field(total.num_lines, config.lines),
-            format_field(total.num_words, config.words),
-            format_field(total.num_bytes, config.bytes),
-            format_field(total.num_chars, config.chars)
-        );
+    if let (Some(cache), true) = (&cache, config.verbose) {
+        eprintln!("cache: {} hits, {} misses", cache.hits(), cache.misses());
+    }
+    let show_total = match config.total {
+        TotalMode::Auto => num_counted > 1,
+        TotalMode::Always | TotalMode::Only => true,
+        TotalMode::Never => false,
+    };
+    if show_total {
+        report.total = Some(Counts::new(&total, &selection));
+    }
+    report.groups = groups
+        .into_iter()
+        .map(|(name, info)| Group {
+            name,
+            counts: Counts::new(&info, &selection),
+        })
+        .collect();
+
+    match config.format {
+        Format::Text => print_text(&config, &files, &report),
+        Format::Json => println!("{}", report.to_json()?),
+        Format::Csv => print!("{}", report.to_delimited(&selection, ',')),
+        Format::Tsv => print!("{}", report.to_delimited(&selection, '\t')),
+    }
+    Ok(all_counted)
+}
+
+// A file that can't be opened or read is reported and left out of the
+// totals, and the other files are still counted
+fn report_failure(report: &mut Report, filename: &str, e: &WcError) {
+    eprintln!("{}: {}", filename, e);
+    report.files.push(Entry::Failed {
+        name: filename.to_string(),
+        error: e.to_string(),
+    });
+}
+
+fn print_completions(target: Completions) -> MyResult<()> {
+    let mut command = Args::command();
+    command.set_bin_name("wcr_v2");
+    command.build();
+    // The generators panic on write errors, so render into memory and let a
+    // closed stdout surface as an ordinary error
+    let mut out = vec![];
+    match target {
+        Completions::Bash => Shell::Bash.try_generate(&command, &mut out)?,
+        Completions::Zsh => Shell::Zsh.try_generate(&command, &mut out)?,
+        Completions::Fish => Shell::Fish.try_generate(&command, &mut out)?,
+        Completions::Man => clap_mangen::Man::new(command).render(&mut out)?,
+    }
+    Ok(io::stdout().write_all(&out)?)
+}
+
+// Puts the path of a file or directory into an I/O error that ends the program
+fn named(filename: &str, e: WcError) -> WcError {
+    match e {
+        WcError::Io(e) => io::Error::new(e.kind(), format!("{}: {}", filename, e)).into(),
+        e => e,
+    }
+}
+
+fn run_diff(config: &Config, source: &DiffSource) -> MyResult<()> {
+    // Unlike the normal mode a failed input ends the comparison, so its
+    // name goes into the error
+    let selection = config.selection();
+    let count = |filename: &str, file: Box<dyn BufRead + '_>| -> MyResult<Counts> {
+        let info = config.counter(filename).count(file).map_err(|e| named(filename, e))?;
+        Ok(Counts::new(&info, &selection))
+    };
+    let open = |filename: &str| open(filename).map_err(|e| named(filename, e));
+    let mut diffs = vec![];
+    match source {
+        DiffSource::Files => {
+            let (old, new) = (&config.files[0], &config.files[1]);
+            let old_counts = count(old, open(old)?)?;
+            let new_counts = count(new, open(new)?)?;
+            diffs.push(Diff::new(old, &old_counts, new, &new_counts));
+        }
+        DiffSource::Git(rev) => {
+            for filename in &config.files {
+                let contents = diff::git_show(rev, filename)?;
+                let old_counts = count(filename, Box::new(contents.as_slice()))?;
+                let new_counts = count(filename, open(filename)?)?;
+                let old = format!("{}:{}", rev, filename);
+                diffs.push(Diff::new(&old, &old_counts, filename, &new_counts));
+            }
+        }
+    }
+
+    match config.format {
+        Format::Text => {
+            let texts: Vec<String> = diffs.iter().map(Diff::to_text).collect();
+            print!("{}", texts.join("\n"));
+        }
+        Format::Json => println!("{}", serde_json::to_string(&diffs)?),
+        Format::Csv => print!("{}", diff::to_delimited(&diffs, ',')),
+        Format::Tsv => print!("{}", diff::to_delimited(&diffs, '\t')),
     }
     Ok(())
 }
 
+fn print_text(config: &Config, files: &[String], report: &Report) {
+    let width = if config.num_fields() == 1 && files.len() == 1 {
+        1
+    } else {
+        column_width(files)
+    };
+    let formatter = FieldFormatter::new(width);
+    for entry in &report.files {
+        if let Entry::Counted { name, counts } = entry {
+            let name = if name == "-" { "" } else { name.as_str() };
+            println!("{}", formatter.format_line(counts, name));
+        }
+    }
+    for group in &report.groups {
+        println!("{}", formatter.format_line(&group.counts, &group.name));
+    }
+    if let Some(counts) = &report.total {
+        // GNU wc leaves the label off when the total is all that is printed
+        let name = if config.total == TotalMode::Only { "" } else { "total" };
+        println!("{}", formatter.format_line(counts, name));
+    }
+
+    let counted = report.files.iter().filter_map(|entry| match entry {
+        Entry::Counted { name, counts } => Some((name.as_str(), counts)),
+        Entry::Failed { .. } => None,
+    });
+    let groups = report.groups.iter().map(|g| (g.name.as_str(), &g.counts));
+    let total = report.total.iter().map(|c| ("total", c));
+    for (name, counts) in counted.chain(groups).chain(total) {
+        let name = if name == "-" { "standard input" } else { name };
+        print!("{}", format_distribution(name, counts));
+    }
+}
+
+fn format_distribution(name: &str, counts: &Counts) -> String {
+    let mut out = String::new();
+    if let Some(buckets) = &counts.line_lengths {
+        out += &format!("{}: line lengths\n", name);
+        let ranges: Vec<String> = buckets.iter().map(|b| format!("{}-{}", b.from, b.to)).collect();
+        let range_width = ranges.iter().map(String::len).max().unwrap_or(0);
+        let lines_width = buckets.iter().map(|b| b.lines).max().unwrap_or(0).to_string().len();
+        for (range, bucket) in ranges.iter().zip(buckets) {
+            out += &format!("  {:>rw$} {:>lw$}\n", range, bucket.lines, rw = range_width, lw = lines_width);
+        }
+    }
+    if let Some(words) = &counts.top_words {
+        out += &format!("{}: top words\n", name);
+        let width = words.first().map_or(0, |w| w.count.to_string().len());
+        for word in words {
+            out += &format!("  {:>width$} {}\n", word.count, word.word, width = width);
+        }
+    }
+    out
+}
+
+// Like GNU wc, size the columns up front from the combined sizes of the
+// regular files, with room for 7 digits when an input such as a pipe has no
+// size to go by.
+fn column_width(files: &[String]) -> usize {
+    let mut size = 0;
+    let mut min_width = 1;
+    for filename in files {
+        let metadata = match filename.as_str() {
+            "-" => stdin_metadata(),
+            _ => fs::metadata(filename),
+        };
+        match metadata {
+            Ok(metadata) if metadata.is_file() => size += metadata.len(),
+            Ok(_) => min_width = 7,
+            Err(_) if filename == "-" => min_width = 7,
+            Err(_) => {}
+        }
+    }
+    size.to_string().len().max(min_width)
+}
+
+#[cfg(unix)]
+fn stdin_metadata() -> io::Result<fs::Metadata> {
+    use std::os::fd::AsFd;
+    File::from(io::stdin().as_fd().try_clone_to_owned()?).metadata()
+}
+
+// Without a descriptor to look at, stdin is taken to be a pipe
+#[cfg(not(unix))]
+fn stdin_metadata() -> io::Result<fs::Metadata> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
 fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
     match filename {
         "-" => Ok(Box::new(BufReader::new(io::stdin()))),
@@ -112,63 +718,51 @@ fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct FileInfo {
-    num_lines: usize,
-    num_words: usize,
-    num_bytes: usize,
-    num_chars: usize,
+fn read_files0_from(list: &str) -> MyResult<Vec<String>> {
+    let reader = match list {
+        "-" => Box::new(BufReader::new(io::stdin())) as Box<dyn BufRead>,
+        _ => Box::new(BufReader::new(File::open(list).map_err(|source| {
+            WcError::FilesFrom {
+                list: list.to_string(),
+                source,
+            }
+        })?)),
+    };
+    parse_files0(reader)
 }
 
-impl FileInfo {
-    fn new() -> Self {
-        FileInfo {
-            num_lines: 0,
-            num_words: 0,
-            num_bytes: 0,
-            num_chars: 0,
-        }
-    }
-    fn add(&mut self, other: &FileInfo) {
-        self.num_lines += other.num_lines;
-        self.num_words += other.num_words;
-        self.num_bytes += other.num_bytes;
-        self.num_chars += other.num_chars;
+fn parse_files0(reader: impl BufRead) -> MyResult<Vec<String>> {
+    let mut names = vec![];
+    for name in reader.split(b'\0') {
+        names.push(String::from_utf8_lossy(&name?).into_owned());
     }
+    Ok(names)
 }
 
-pub fn count(mut file: impl BufRead) -> MyResult<FileInfo> {
-    let mut num_lines = 0;
-    let mut num_words = 0;
-    let mut num_bytes = 0;
-    let mut num_chars = 0;
+struct FieldFormatter {
+    width: usize,
+}
 
-    let mut line = String::new();
-    loop {
-        let line_bytes = file.read_line(&mut line)?;
-        if line_bytes == 0 {
-            break; // EOF
-        }
-        num_bytes += line_bytes;
-        num_lines += 1;
-        num_words += line.split_whitespace().count();
-        num_chars += line.chars().count();
-        line.clear(); // Clear the line for the next read
+impl FieldFormatter {
+    fn new(width: usize) -> Self {
+        FieldFormatter { width }
     }
 
-    Ok(FileInfo {
-        num_lines,
-        num_words,
-        num_bytes,
-        num_chars,
-    })
-}
+    fn format_field(&self, value: usize) -> String {
+        format!("{:>width$}", value, width = self.width)
+    }
 
-fn format_field(value: usize, show: bool) -> String {
-    if show {
-        format!("{:>8}", value)
-    } else {
-        String::new()
+    fn format_line(&self, counts: &Counts, name: &str) -> String {
+        let fields: Vec<String> = counts
+            .fields()
+            .into_iter()
+            .map(|(_, value)| self.format_field(value))
+            .collect();
+        if name.is_empty() {
+            fields.join(" ")
+        } else {
+            format!("{} {}", fields.join(" "), name)
+        }
     }
 }
 
@@ -179,23 +773,38 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_count() {
-        let text = "I don't want the world, I just want your half.\r\n";
-        let info = count(Cursor::new(text));
-        assert!(info.is_ok());
-        let expected = FileInfo {
-            num_lines: 1,
-            num_words: 10, // "I", "don't", "want", "the", "world,", "I", "just", "want", "your", "half."
-            num_bytes: text.len(),
-            num_chars: text.chars().count(),
-        };
-        assert_eq!(info.unwrap(), expected);
+    fn test_parse_files0() {
+        let names = parse_files0(Cursor::new("a.txt\0\0b c.txt\0"));
+        assert!(names.is_ok());
+        assert_eq!(names.unwrap(), vec!["a.txt", "", "b c.txt"]);
+
+        let names = parse_files0(Cursor::new("a.txt"));
+        assert_eq!(names.unwrap(), vec!["a.txt"]);
+
+        let names = parse_files0(Cursor::new(""));
+        assert!(names.unwrap().is_empty());
     }
 
     #[test]
     fn test_format_field() {
-        assert_eq!(format_field(1, false), "");
-        assert_eq!(format_field(3, true), "       3");
-        assert_eq!(format_field(10, true), "      10");
+        let formatter = FieldFormatter::new(8);
+        assert_eq!(formatter.format_field(3), "       3");
+        assert_eq!(formatter.format_field(10), "      10");
+
+        let formatter = FieldFormatter::new(1);
+        assert_eq!(formatter.format_field(10), "10");
+    }
+
+    #[test]
+    fn test_format_line() {
+        let counts = Counts {
+            lines: Some(1),
+            words: Some(9),
+            bytes: Some(48),
+            ..Counts::default()
+        };
+        let formatter = FieldFormatter::new(3);
+        assert_eq!(formatter.format_line(&counts, "fox.txt"), "  1   9  48 fox.txt");
+        assert_eq!(formatter.format_line(&counts, ""), "  1   9  48");
     }
 }
\ No newline at end of file
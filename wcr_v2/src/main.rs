@@ -1,6 +1,10 @@
 fn main() {
-    if let Err(e) = wcr_v2::get_args().and_then(wcr_v2::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
-    } 
+    match wcr_v2::get_args().and_then(wcr_v2::run) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,94 @@
+use clap::ValueEnum;
+use std::path::Path;
+
+/// The extra counters that can be enabled with `--metrics`.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Metric {
+    Blank,
+    #[value(name = "nonblank")]
+    NonBlank,
+    Paragraphs,
+    Sentences,
+    Comments,
+}
+
+const COMMENT_PREFIXES: &[(&[&str], &str)] = &[
+    (
+        &["rs", "c", "h", "cc", "cpp", "hpp", "cs", "java", "js", "ts", "go", "kt", "swift", "scala"],
+        "//",
+    ),
+    (
+        &["py", "sh", "bash", "zsh", "rb", "pl", "r", "toml", "yaml", "yml", "mk", "cmake"],
+        "#",
+    ),
+    (&["sql", "lua", "hs", "elm", "ada"], "--"),
+    (&["lisp", "el", "clj", "scm", "asm", "ini"], ";"),
+    (&["tex", "erl", "m"], "%"),
+    (&["vim"], "\""),
+];
+
+/// Looks up the line comment prefix for a file by its extension. Block
+/// comments are not recognized.
+pub fn comment_prefix(filename: &str) -> Option<&'static str> {
+    let ext = Path::new(filename).extension()?.to_str()?.to_lowercase();
+    COMMENT_PREFIXES
+        .iter()
+        .find(|(exts, _)| exts.contains(&ext.as_str()))
+        .map(|(_, prefix)| *prefix)
+}
+
+fn is_terminal(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '。' | '！' | '？')
+}
+
+fn is_closing(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '”' | '’' | '」' | '』')
+}
+
+/// Counts sentence endings in a line: a run of terminal punctuation that is
+/// followed by whitespace, a closing quote or bracket, or the end of the line.
+pub fn count_sentences(line: &str) -> usize {
+    let mut num_sentences = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !is_terminal(c) {
+            continue;
+        }
+        while chars.next_if(|c| is_terminal(*c)).is_some() {}
+        match chars.peek() {
+            None => num_sentences += 1,
+            Some(next) if next.is_whitespace() || is_closing(*next) => num_sentences += 1,
+            _ => {}
+        }
+    }
+    num_sentences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_prefix() {
+        assert_eq!(comment_prefix("src/lib.rs"), Some("//"));
+        assert_eq!(comment_prefix("setup.PY"), Some("#"));
+        assert_eq!(comment_prefix("query.sql"), Some("--"));
+        assert_eq!(comment_prefix("notes.txt"), None);
+        assert_eq!(comment_prefix("-"), None);
+    }
+
+    #[test]
+    fn test_count_sentences() {
+        assert_eq!(count_sentences("Hello. How are you?\n"), 2);
+        assert_eq!(count_sentences("Wait... what?!\n"), 2);
+        assert_eq!(count_sentences("Version 1.2 is out\n"), 0);
+        assert_eq!(count_sentences("\"Stop!\" she said."), 2);
+        assert_eq!(count_sentences("これはペンです。"), 1);
+    }
+
+    #[test]
+    fn test_invalid_metric() {
+        assert!(Metric::from_str("lines", false).is_err());
+        assert_eq!(Metric::from_str("nonblank", false), Ok(Metric::NonBlank));
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::{Counter, WordSplitter};
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Reports partial counts on stderr while an input is being counted: a
+/// status line redrawn in place with `--progress`, and a one-off line
+/// whenever SIGUSR1 arrives, as `dd` does.
+pub struct Progress {
+    redraw: bool,
+    current: Arc<Mutex<Current>>,
+    last_draw: Option<Instant>,
+}
+
+/// The input being counted and its counts so far, updated after every
+/// chunk so the SIGUSR1 thread can report them while a read is blocked.
+#[derive(Debug, Default)]
+struct Current {
+    name: Option<String>,
+    start: Option<Instant>,
+    lines: usize,
+    words: usize,
+    bytes: usize,
+}
+
+impl Progress {
+    /// The status line is only drawn when stderr is a terminal. On Unix
+    /// this also starts answering SIGUSR1, so create it before any work
+    /// that a signal shouldn't interrupt.
+    pub fn new(enabled: bool) -> Self {
+        let redraw = enabled && io::stderr().is_terminal();
+        let current = Arc::new(Mutex::new(Current::default()));
+        #[cfg(unix)]
+        report_on_sigusr1(Arc::clone(&current), redraw);
+        Progress {
+            redraw,
+            current,
+            last_draw: None,
+        }
+    }
+
+    /// Starts timing a new input.
+    pub fn start(&mut self, name: &str) {
+        *self.current() = Current {
+            name: Some(name.to_string()),
+            start: Some(Instant::now()),
+            ..Current::default()
+        };
+        self.last_draw = None;
+    }
+
+    /// Called after every chunk with the counter's state so far.
+    pub fn update<W: WordSplitter>(&mut self, counter: &Counter<W>) {
+        let status = {
+            let mut current = self.current();
+            current.lines = counter.partial().num_lines();
+            current.words = counter.partial().num_words();
+            current.bytes = counter.bytes_seen();
+            let due = self
+                .last_draw
+                .is_none_or(|last| last.elapsed() >= REDRAW_INTERVAL);
+            if !(self.redraw && due) {
+                return;
+            }
+            current.status()
+        };
+        if let Some(status) = status {
+            let mut stderr = io::stderr().lock();
+            let _ = write!(stderr, "\r{}\x1b[K", status);
+            let _ = stderr.flush();
+            self.last_draw = Some(Instant::now());
+        }
+    }
+
+    /// Clears the status line once an input is done.
+    pub fn finish(&mut self) {
+        self.current().name = None;
+        if self.redraw && self.last_draw.is_some() {
+            let _ = write!(io::stderr(), "\r\x1b[K");
+        }
+    }
+
+    fn current(&self) -> MutexGuard<'_, Current> {
+        self.current.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Current {
+    fn status(&self) -> Option<String> {
+        let name = self.name.as_deref()?;
+        let secs = self.start.map_or(0.0, |start| start.elapsed().as_secs_f64());
+        let rate = if secs > 0.0 { self.bytes as f64 / secs } else { 0.0 };
+        Some(format!(
+            "{}: {} lines, {} words, {} bytes, {:.1} s, {}/s",
+            if name == "-" { "standard input" } else { name },
+            self.lines,
+            self.words,
+            self.bytes,
+            secs,
+            human_bytes(rate)
+        ))
+    }
+}
+
+// Reports from a thread of its own, so a read that is stuck waiting for
+// input doesn't hold the report back. Between inputs there is nothing to
+// report and the signal is just swallowed.
+#[cfg(unix)]
+fn report_on_sigusr1(current: Arc<Mutex<Current>>, redraw: bool) {
+    use signal_hook::{consts::SIGUSR1, iterator::Signals};
+
+    let mut signals = match Signals::new([SIGUSR1]) {
+        Ok(signals) => signals,
+        Err(e) => {
+            eprintln!("cannot install the SIGUSR1 handler: {}", e);
+            return;
+        }
+    };
+    std::thread::spawn(move || {
+        for _ in signals.forever() {
+            let status = current.lock().unwrap_or_else(|e| e.into_inner()).status();
+            if let Some(status) = status {
+                // Writes over the status line, which the next update redraws
+                let clear = if redraw { "\r\x1b[K" } else { "" };
+                let _ = writeln!(io::stderr().lock(), "{}{}", clear, status);
+            }
+        }
+    });
+}
+
+fn human_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_bytes() {
+        assert_eq!(human_bytes(0.0), "0 B");
+        assert_eq!(human_bytes(1023.0), "1023 B");
+        assert_eq!(human_bytes(1536.0), "1.5 KiB");
+        assert_eq!(human_bytes(3.0 * 1024.0 * 1024.0 * 1024.0), "3.0 GiB");
+    }
+}
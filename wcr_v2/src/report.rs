@@ -0,0 +1,259 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::{Bucket, FileInfo, Metric, WordCount};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+    Tsv,
+}
+
+/// How `--group-by` buckets files into subtotals.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum GroupBy {
+    Ext,
+    Dir,
+}
+
+impl GroupBy {
+    /// The subtotal a file belongs to: `*.rs` for extensions or `src/` for
+    /// directories, so group names can't be mistaken for files.
+    pub fn key(&self, filename: &str) -> String {
+        let path = Path::new(filename);
+        let key = match self {
+            _ if filename == "-" => None,
+            GroupBy::Ext => path
+                .extension()
+                .map(|ext| format!("*.{}", ext.to_string_lossy())),
+            GroupBy::Dir => path.parent().map(|dir| match dir.to_string_lossy() {
+                dir if dir.is_empty() => "./".to_string(),
+                dir => format!("{}/", dir),
+            }),
+        };
+        key.unwrap_or_else(|| "(none)".to_string())
+    }
+}
+
+/// Which counters go into a `Counts`; `top_words` is how many words to list.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Selection {
+    pub lines: bool,
+    pub words: bool,
+    pub bytes: bool,
+    pub chars: bool,
+    pub metrics: Vec<Metric>,
+    pub line_lengths: bool,
+    pub top_words: Option<usize>,
+}
+
+/// The selected counters of a `FileInfo`; counters that were not asked for
+/// are `None` and left out of every output format.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Counts {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub words: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chars: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blank: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonblank: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paragraphs: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sentences: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_lengths: Option<Vec<Bucket>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_words: Option<Vec<WordCount>>,
+}
+
+impl Counts {
+    pub fn new(info: &FileInfo, selection: &Selection) -> Self {
+        let metric = |metric, value| selection.metrics.contains(&metric).then_some(value);
+        Counts {
+            lines: selection.lines.then_some(info.num_lines()),
+            words: selection.words.then_some(info.num_words()),
+            bytes: selection.bytes.then_some(info.num_bytes()),
+            chars: selection.chars.then_some(info.num_chars()),
+            blank: metric(Metric::Blank, info.num_blank()),
+            nonblank: metric(Metric::NonBlank, info.num_nonblank()),
+            paragraphs: metric(Metric::Paragraphs, info.num_paragraphs()),
+            sentences: metric(Metric::Sentences, info.num_sentences()),
+            comments: metric(Metric::Comments, info.num_comments()),
+            line_lengths: selection.line_lengths.then(|| info.distribution().buckets()),
+            top_words: selection.top_words.map(|n| info.distribution().top_words(n)),
+        }
+    }
+
+    /// The selected counters with their names, in output order.
+    pub fn fields(&self) -> Vec<(&'static str, usize)> {
+        [
+            ("lines", self.lines),
+            ("words", self.words),
+            ("bytes", self.bytes),
+            ("chars", self.chars),
+            ("blank", self.blank),
+            ("nonblank", self.nonblank),
+            ("paragraphs", self.paragraphs),
+            ("sentences", self.sentences),
+            ("comments", self.comments),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Entry {
+    Counted {
+        name: String,
+        #[serde(flatten)]
+        counts: Counts,
+    },
+    Failed {
+        name: String,
+        error: String,
+    },
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Group {
+    pub name: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Report {
+    pub files: Vec<Entry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<Counts>,
+}
+
+impl Report {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Renders a header and one row per counted file (plus the groups and
+    /// the total) with the given delimiter. Failed files are only reported
+    /// on stderr.
+    pub fn to_delimited(&self, selection: &Selection, delimiter: char) -> String {
+        let header: Vec<&str> = Counts::new(&FileInfo::new(), selection)
+            .fields()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+
+        let mut rows = vec![format!("name{}{}", delimiter, header.join(&delimiter.to_string()))];
+        let counted = self.files.iter().filter_map(|entry| match entry {
+            Entry::Counted { name, counts } => Some((name.as_str(), counts)),
+            Entry::Failed { .. } => None,
+        });
+        let groups = self.groups.iter().map(|g| (g.name.as_str(), &g.counts));
+        let total = self.total.iter().map(|c| ("total", c));
+        for (name, counts) in counted.chain(groups).chain(total) {
+            let mut row = vec![escape_field(name, delimiter)];
+            row.extend(counts.fields().iter().map(|(_, value)| value.to_string()));
+            rows.push(row.join(&delimiter.to_string()));
+        }
+        rows.iter().map(|row| format!("{}\n", row)).collect()
+    }
+}
+
+// CSV fields are quoted as in RFC 4180; TSV has no quoting, so the
+// characters that would break a row are backslash-escaped instead.
+pub(crate) fn escape_field(field: &str, delimiter: char) -> String {
+    if delimiter == '\t' {
+        field
+            .replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+    } else if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selection() -> Selection {
+        Selection { lines: true, bytes: true, ..Selection::default() }
+    }
+
+    fn report() -> Report {
+        Report {
+            files: vec![
+                Entry::Counted {
+                    name: "a,b.txt".to_string(),
+                    counts: Counts { lines: Some(1), bytes: Some(48), ..Counts::default() },
+                },
+                Entry::Failed {
+                    name: "missing".to_string(),
+                    error: "No such file or directory (os error 2)".to_string(),
+                },
+            ],
+            groups: vec![],
+            total: Some(Counts { lines: Some(1), bytes: Some(48), ..Counts::default() }),
+        }
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            report().to_json().unwrap(),
+            concat!(
+                r#"{"files":[{"name":"a,b.txt","lines":1,"bytes":48},"#,
+                r#"{"name":"missing","error":"No such file or directory (os error 2)"}],"#,
+                r#""total":{"lines":1,"bytes":48}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_to_delimited() {
+        assert_eq!(
+            report().to_delimited(&selection(), ','),
+            "name,lines,bytes\n\"a,b.txt\",1,48\ntotal,1,48\n"
+        );
+        assert_eq!(
+            report().to_delimited(&selection(), '\t'),
+            "name\tlines\tbytes\na,b.txt\t1\t48\ntotal\t1\t48\n"
+        );
+    }
+
+    #[test]
+    fn test_group_key() {
+        assert_eq!(GroupBy::Ext.key("src/lib.rs"), "*.rs");
+        assert_eq!(GroupBy::Ext.key("Makefile"), "(none)");
+        assert_eq!(GroupBy::Dir.key("src/lib.rs"), "src/");
+        assert_eq!(GroupBy::Dir.key("lib.rs"), "./");
+        assert_eq!(GroupBy::Dir.key("-"), "(none)");
+    }
+
+    #[test]
+    fn test_escape_field() {
+        assert_eq!(escape_field("plain", ','), "plain");
+        assert_eq!(escape_field("say \"hi\"", ','), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_field("a\tb", '\t'), "a\\tb");
+    }
+}
@@ -0,0 +1,171 @@
+use std::io::{self, Write};
+
+use crate::{Counter, WordSplitter};
+
+/// Passes everything written through to `inner` while counting it, e.g.
+/// to count the bytes a proxy forwards.
+#[derive(Debug)]
+pub struct CountingWriter<T, W> {
+    inner: T,
+    counter: Counter<W>,
+}
+
+impl<T: Write, W: WordSplitter> CountingWriter<T, W> {
+    pub fn new(inner: T, counter: Counter<W>) -> Self {
+        CountingWriter { inner, counter }
+    }
+
+    pub fn counter(&self) -> &Counter<W> {
+        &self.counter
+    }
+
+    pub fn counter_mut(&mut self) -> &mut Counter<W> {
+        &mut self.counter
+    }
+
+    pub fn into_parts(self) -> (T, Counter<W>) {
+        (self.inner, self.counter)
+    }
+}
+
+impl<T: Write, W: WordSplitter> Write for CountingWriter<T, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.counter.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "tokio")]
+pub use self::tokio_reader::CountingReader;
+
+#[cfg(feature = "tokio")]
+mod tokio_reader {
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    use crate::{Counter, WordSplitter};
+
+    /// Counts everything read through it from an async source.
+    #[derive(Debug)]
+    pub struct CountingReader<R, W> {
+        inner: R,
+        counter: Counter<W>,
+    }
+
+    impl<R: AsyncRead + Unpin, W: WordSplitter + Unpin> CountingReader<R, W> {
+        pub fn new(inner: R, counter: Counter<W>) -> Self {
+            CountingReader { inner, counter }
+        }
+
+        pub fn counter(&self) -> &Counter<W> {
+            &self.counter
+        }
+
+        pub fn counter_mut(&mut self) -> &mut Counter<W> {
+            &mut self.counter
+        }
+
+        pub fn into_parts(self) -> (R, Counter<W>) {
+            (self.inner, self.counter)
+        }
+    }
+
+    impl<R: AsyncRead + Unpin, W: WordSplitter + Unpin> AsyncRead for CountingReader<R, W> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            let this = self.get_mut();
+            let before = buf.filled().len();
+            let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+            if let Poll::Ready(Ok(())) = poll {
+                this.counter.update(&buf.filled()[before..]);
+            }
+            poll
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Read},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_counting_writer_over_socket() -> io::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let sender = thread::spawn(move || -> io::Result<()> {
+            let mut stream = TcpStream::connect(addr)?;
+            for _ in 0..1000 {
+                stream.write_all(b"The quick brown fox\n")?;
+            }
+            Ok(())
+        });
+
+        let (mut stream, _) = listener.accept()?;
+        let mut proxied = vec![];
+        let mut writer = CountingWriter::new(&mut proxied, Counter::new());
+        let mut buf = [0; 7]; // odd-sized chunks split lines and words
+        loop {
+            let n = stream.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&buf[..n])?;
+        }
+        sender.join().unwrap()?;
+
+        let (_, mut counter) = writer.into_parts();
+        let info = counter.finish();
+        assert_eq!(proxied.len(), 20_000);
+        assert_eq!(info.num_bytes(), 20_000);
+        assert_eq!(info.num_lines(), 1000);
+        assert_eq!(info.num_words(), 4000);
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_counting_reader_over_socket() -> io::Result<()> {
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::{TcpListener, TcpStream},
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let sender = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await?;
+            stream.write_all(b"one two\nthree\nfour").await?;
+            stream.shutdown().await
+        });
+
+        let (stream, _) = listener.accept().await?;
+        let mut reader = CountingReader::new(stream, Counter::new());
+        let mut received = vec![];
+        reader.read_to_end(&mut received).await?;
+        sender.await??;
+
+        let info = reader.counter_mut().finish();
+        assert_eq!(received.len(), 18);
+        assert_eq!(info.num_bytes(), 18);
+        assert_eq!(info.num_lines(), 3);
+        assert_eq!(info.num_words(), 4);
+        Ok(())
+    }
+}
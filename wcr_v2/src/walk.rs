@@ -0,0 +1,86 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// How `--recursive` expands directory arguments into files.
+#[derive(Debug)]
+pub struct WalkOptions {
+    pub include: GlobSet,
+    pub exclude: GlobSet,
+}
+
+impl WalkOptions {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, globset::Error> {
+        Ok(WalkOptions {
+            include: build_globset(include)?,
+            exclude: build_globset(exclude)?,
+        })
+    }
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    builder.build()
+}
+
+/// Replaces every directory in `files` with the text files below it, in
+/// name order. Walks honour `.gitignore` and skip hidden entries; other
+/// arguments are passed through untouched. Entries that can't be read are
+/// reported on stderr and make the returned flag false.
+pub fn expand(files: &[String], options: &WalkOptions) -> (Vec<String>, bool) {
+    let mut expanded = vec![];
+    let mut all_read = true;
+    for filename in files {
+        if filename == "-" || !Path::new(filename).is_dir() {
+            expanded.push(filename.clone());
+            continue;
+        }
+        let exclude = options.exclude.clone();
+        let walker = WalkBuilder::new(filename)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| entry.depth() == 0 || !exclude.is_match(entry.path()))
+            .build();
+        for entry in walker {
+            match entry {
+                Err(e) => {
+                    eprintln!("{}", e);
+                    all_read = false;
+                }
+                Ok(entry) => {
+                    let path = entry.path();
+                    if !entry.file_type().is_some_and(|t| t.is_file()) {
+                        continue;
+                    }
+                    if !options.include.is_empty() && !options.include.is_match(path) {
+                        continue;
+                    }
+                    match is_binary(path) {
+                        Err(e) => {
+                            eprintln!("{}: {}", path.display(), e);
+                            all_read = false;
+                        }
+                        Ok(true) => {}
+                        Ok(false) => expanded.push(path.to_string_lossy().into_owned()),
+                    }
+                }
+            }
+        }
+    }
+    (expanded, all_read)
+}
+
+/// Like git and grep, treats a file as binary if its first block holds a NUL byte.
+fn is_binary(path: &Path) -> io::Result<bool> {
+    let mut buffer = [0; 8192];
+    let mut file = File::open(path)?;
+    let bytes_read = file.read(&mut buffer)?;
+    Ok(buffer[..bytes_read].contains(&0))
+}
@@ -0,0 +1,179 @@
+use regex::Regex;
+use std::str::FromStr;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Splits a line of text into words. `count` is generic over this trait so
+/// library callers can plug in their own definition of a word.
+pub trait WordSplitter {
+    fn words<'a>(&'a self, line: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a>;
+
+    fn count_words(&self, line: &str) -> usize {
+        self.words(line).count()
+    }
+}
+
+impl<S: WordSplitter + ?Sized> WordSplitter for &S {
+    fn words<'a>(&'a self, line: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        (**self).words(line)
+    }
+
+    fn count_words(&self, line: &str) -> usize {
+        (**self).count_words(line)
+    }
+}
+
+/// Words are runs of non-whitespace, as in `wc`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Whitespace;
+
+impl WordSplitter for Whitespace {
+    fn words<'a>(&'a self, line: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        Box::new(line.split_whitespace())
+    }
+
+    // Plain `wc` counts every line this way, so skip the boxed iterator
+    fn count_words(&self, line: &str) -> usize {
+        line.split_whitespace().count()
+    }
+}
+
+/// Words as given by the UAX #29 word boundaries, without punctuation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeWords;
+
+impl WordSplitter for UnicodeWords {
+    fn words<'a>(&'a self, line: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        Box::new(line.unicode_words())
+    }
+}
+
+/// Every match of the pattern is a word.
+#[derive(Debug, Clone)]
+pub struct RegexWords(pub Regex);
+
+impl WordSplitter for RegexWords {
+    fn words<'a>(&'a self, line: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        Box::new(self.0.find_iter(line).map(|m| m.as_str()))
+    }
+}
+
+/// Words are separated by any of the given characters. Line endings always
+/// separate words.
+#[derive(Debug, Clone)]
+pub struct SeparatorWords(pub Vec<char>);
+
+impl WordSplitter for SeparatorWords {
+    fn words<'a>(&'a self, line: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        Box::new(
+            line.split(|c: char| c == '\n' || c == '\r' || self.0.contains(&c))
+                .filter(|word| !word.is_empty()),
+        )
+    }
+}
+
+/// The word definition chosen with `--word-mode`.
+#[derive(Debug, Clone)]
+pub enum WordMode {
+    Whitespace,
+    Unicode,
+    Regex(RegexWords),
+    Separators(SeparatorWords),
+}
+
+impl FromStr for WordMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "whitespace" => Ok(WordMode::Whitespace),
+            "unicode" => Ok(WordMode::Unicode),
+            _ => {
+                if let Some(pattern) = s.strip_prefix("regex:") {
+                    Regex::new(pattern)
+                        .map(|re| WordMode::Regex(RegexWords(re)))
+                        .map_err(|e| format!("invalid word regex '{}': {}", pattern, e))
+                } else if let Some(chars) = s.strip_prefix("sep:") {
+                    if chars.is_empty() {
+                        Err("no word separators given".to_string())
+                    } else {
+                        Ok(WordMode::Separators(SeparatorWords(chars.chars().collect())))
+                    }
+                } else {
+                    Err(format!("invalid word mode '{}'", s))
+                }
+            }
+        }
+    }
+}
+
+impl WordSplitter for WordMode {
+    fn words<'a>(&'a self, line: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        match self {
+            WordMode::Whitespace => Whitespace.words(line),
+            WordMode::Unicode => UnicodeWords.words(line),
+            WordMode::Regex(splitter) => splitter.words(line),
+            WordMode::Separators(splitter) => splitter.words(line),
+        }
+    }
+
+    fn count_words(&self, line: &str) -> usize {
+        match self {
+            WordMode::Whitespace => Whitespace.count_words(line),
+            WordMode::Unicode => UnicodeWords.count_words(line),
+            WordMode::Regex(splitter) => splitter.count_words(line),
+            WordMode::Separators(splitter) => splitter.count_words(line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words<'a>(mode: &'a str, line: &'a str) -> Vec<String> {
+        let mode: WordMode = mode.parse().unwrap();
+        mode.words(line).map(String::from).collect()
+    }
+
+    #[test]
+    fn test_whitespace() {
+        assert_eq!(words("whitespace", " foo  bar,baz\n"), vec!["foo", "bar,baz"]);
+    }
+
+    #[test]
+    fn test_unicode() {
+        assert_eq!(words("unicode", "Hello, world!\n"), vec!["Hello", "world"]);
+        assert_eq!(words("unicode", "日本語"), vec!["日", "本", "語"]);
+    }
+
+    #[test]
+    fn test_regex() {
+        assert_eq!(
+            words("regex:[A-Za-z_][A-Za-z0-9_]*", "let x_1 = foo(2);\n"),
+            vec!["let", "x_1", "foo"]
+        );
+    }
+
+    #[test]
+    fn test_separators() {
+        assert_eq!(words("sep:,;", "a,b;;c\n"), vec!["a", "b", "c"]);
+        assert_eq!(words("sep:,", "a b,c\r\n"), vec!["a b", "c"]);
+    }
+
+    #[test]
+    fn test_count_words() {
+        let line = "  one, two\tthree;four 日本\r\n";
+        for mode in ["whitespace", "unicode", r"regex:\w+", "sep:,;"] {
+            let mode: WordMode = mode.parse().unwrap();
+            assert_eq!(mode.count_words(line), mode.words(line).count());
+        }
+        assert_eq!(Whitespace.count_words(line), 4);
+    }
+
+    #[test]
+    fn test_invalid_modes() {
+        assert!("bogus".parse::<WordMode>().is_err());
+        assert!("sep:".parse::<WordMode>().is_err());
+        assert!("regex:(".parse::<WordMode>().is_err());
+    }
+}
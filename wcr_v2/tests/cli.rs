@@ -1,7 +1,7 @@
-use assert_cmd::Command;
+use assert_cmd::{Command, assert::OutputAssertExt, cargo::CommandCargoExt};
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
-use std::fs;
+use std::{fs, path::Path};
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
@@ -9,6 +9,7 @@ const PRG: &str = "wcr_v2";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
+const CODE: &str = "tests/inputs/code.rs";
 
 // --------------------------------------------------
 fn gen_bad_file() -> String {
@@ -29,12 +30,12 @@ fn gen_bad_file() -> String {
 #[test]
 fn dies_chars_and_bytes() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["-m", "-c"])
+        .args(["-m", "-c"])
         .assert()
         .failure()
-        .stderr(predicate::str::contains(
-            "The argument '--bytes' cannot be used with '--chars'",
-        ));
+        .stderr(predicate::str::is_match(
+            "the argument '--(bytes|chars)' cannot be used with '--(chars|bytes)'",
+        )?);
     Ok(())
 }
 
@@ -57,7 +58,7 @@ fn skips_bad_file() -> TestResult {
     Command::cargo_bin(PRG)?
         .arg(bad)
         .assert()
-        .success()
+        .failure()
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -161,9 +162,22 @@ fn atlamal_bytes_lines() -> TestResult {
 // --------------------------------------------------
 #[test]
 fn atlamal_stdin() -> TestResult {
-    let input = fs::read_to_string(ATLAMAL)?;
     let expected =
         fs::read_to_string("tests/expected/atlamal.txt.stdin.out")?;
+    // Redirected from the file itself, so stdin is a regular file
+    std::process::Command::cargo_bin(PRG)?
+        .stdin(fs::File::open(ATLAMAL)?)
+        .assert()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn atlamal_pipe() -> TestResult {
+    let input = fs::read_to_string(ATLAMAL)?;
+    let expected =
+        fs::read_to_string("tests/expected/atlamal.txt.pipe.out")?;
     Command::cargo_bin(PRG)?
         .write_stdin(input)
         .assert()
@@ -212,3 +226,865 @@ fn test_all_words_lines() -> TestResult {
 fn test_all_bytes_lines() -> TestResult {
     run(&["-cl", EMPTY, FOX, ATLAMAL], "tests/expected/all.cl.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn test_all_files0_from() -> TestResult {
+    run(
+        &["--files0-from", "tests/inputs/files0.txt"],
+        "tests/expected/all.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_files0_from_stdin() -> TestResult {
+    let input = fs::read("tests/inputs/files0.txt")?;
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    Command::cargo_bin(PRG)?
+        .arg("--files0-from=-")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_empty_name() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("--files0-from=-")
+        .write_stdin(format!("{}\0\0", FOX))
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(FOX))
+        .stderr("-:2: invalid zero-length file name\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin_dash() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("--files0-from=-")
+        .write_stdin("-\0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "when reading file names from standard input, no file name of '-' allowed",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_with_operand() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "tests/inputs/files0.txt", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "file operands cannot be combined with --files0-from",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("cannot open '{}' for reading: .* [(]os error 2[)]", bad);
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_json() -> TestResult {
+    run(&["--format", "json", EMPTY, FOX, ATLAMAL], "tests/expected/all.json.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_csv() -> TestResult {
+    run(&["--format", "csv", EMPTY, FOX, ATLAMAL], "tests/expected/all.csv.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_tsv() -> TestResult {
+    run(&["--format", "tsv", EMPTY, FOX, ATLAMAL], "tests/expected/all.tsv.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn json_reports_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!(
+        r#"^\{{"files":\[\{{"name":"{}","error":".* \(os error 2\)"\}},\{{"name":"{}","lines":1\}}\]\}}\n$"#,
+        bad, FOX
+    );
+    Command::cargo_bin(PRG)?
+        .args(["--format", "json", "-l", &bad, FOX])
+        .assert()
+        .stdout(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format", "xml", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid value 'xml' for '--format <FORMAT>'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_total_always() -> TestResult {
+    run(&["--total=always", FOX], "tests/expected/fox.txt.total-always.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_total_never() -> TestResult {
+    run(
+        &["--total=never", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.total-never.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_total_only() -> TestResult {
+    run(
+        &["--total", "only", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.total-only.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn total_auto_ignores_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/fox.txt.out")?;
+    Command::cargo_bin(PRG)?
+        .args([FOX, &bad])
+        .assert()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_word_mode_sep() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-w", "--word-mode", "sep:e", FOX])
+        .assert()
+        .success()
+        .stdout(format!("4 {}\n", FOX));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn word_mode_regex() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-w", "--word-mode=regex:[A-Za-z_][A-Za-z0-9_]*"])
+        .write_stdin("let x_1 = foo(2);\n")
+        .assert()
+        .success()
+        .stdout("3\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn word_mode_unicode() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-w", "--word-mode", "unicode"])
+        .write_stdin("日本語のテキスト, hello!\n")
+        .assert()
+        .success()
+        .stdout("6\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_word_mode() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--word-mode", "regex:(", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid word regex '('"));
+    Command::cargo_bin(PRG)?
+        .args(["--word-mode", "bogus", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'bogus' for '--word-mode <MODE>': invalid word mode 'bogus'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn code_metrics() -> TestResult {
+    run(
+        &["--metrics", "blank,nonblank,paragraphs,sentences,comments", CODE, ATLAMAL],
+        "tests/expected/code.rs.metrics.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn code_metrics_json() -> TestResult {
+    run(
+        &[
+            "--format=json",
+            "--metrics=blank,nonblank,paragraphs,sentences,comments",
+            CODE,
+            ATLAMAL,
+        ],
+        "tests/expected/code.rs.metrics.json.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn code_metrics_tsv() -> TestResult {
+    run(
+        &["--format", "tsv", "-l", "--metrics", "comments", "--metrics", "blank", CODE],
+        "tests/expected/code.rs.metrics.tsv.out",
+    )
+}
+
+// --------------------------------------------------
+fn make_tree() -> Result<tempfile::TempDir, Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    fs::create_dir(root.join("sub"))?;
+    fs::write(root.join(".gitignore"), "*.log\n")?;
+    fs::write(root.join("a.rs"), "fn a() {}\n")?;
+    fs::write(root.join("b.txt"), "one two\nthree\n")?;
+    fs::write(root.join("build.log"), "ignored\n")?;
+    fs::write(root.join("data.bin"), b"\x00\x01binary\n")?;
+    fs::write(root.join("sub").join("c.rs"), "fn c() {}\nfn d() {}\n")?;
+    Ok(dir)
+}
+
+fn path(root: &Path, name: &str) -> String {
+    root.join(name).display().to_string()
+}
+
+fn tsv(rows: &[(String, &str)]) -> String {
+    let mut expected = String::from("name\tlines\twords\tbytes\n");
+    for (name, counts) in rows {
+        expected.push_str(&format!("{}\t{}\n", name, counts));
+    }
+    expected
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive() -> TestResult {
+    let dir = make_tree()?;
+    let root = dir.path();
+    Command::cargo_bin(PRG)?
+        .args(["-r", "--format", "tsv"])
+        .arg(root)
+        .assert()
+        .success()
+        .stdout(tsv(&[
+            (path(root, "a.rs"), "1\t3\t10"),
+            (path(root, "b.txt"), "2\t3\t14"),
+            (path(root, "sub/c.rs"), "2\t6\t20"),
+            ("total".to_string(), "5\t12\t44"),
+        ]));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_include_exclude() -> TestResult {
+    let dir = make_tree()?;
+    let root = dir.path();
+    Command::cargo_bin(PRG)?
+        .args(["-r", "--format=tsv", "--include", "*.rs", "--exclude", "*/sub"])
+        .arg(root)
+        .assert()
+        .success()
+        .stdout(tsv(&[(path(root, "a.rs"), "1\t3\t10")]));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_group_by_ext() -> TestResult {
+    let dir = make_tree()?;
+    let root = dir.path();
+    Command::cargo_bin(PRG)?
+        .args(["-r", "--format=tsv", "--total=never", "--group-by=ext"])
+        .arg(root)
+        .assert()
+        .success()
+        .stdout(tsv(&[
+            (path(root, "a.rs"), "1\t3\t10"),
+            (path(root, "b.txt"), "2\t3\t14"),
+            (path(root, "sub/c.rs"), "2\t6\t20"),
+            ("*.rs".to_string(), "3\t9\t30"),
+            ("*.txt".to_string(), "2\t3\t14"),
+        ]));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_files0_from_empty_name() -> TestResult {
+    let dir = make_tree()?;
+    let root = dir.path();
+    Command::cargo_bin(PRG)?
+        .args(["-r", "--format=tsv", "--total=never", "--files0-from=-"])
+        .write_stdin(format!("{}\0\0", root.display()))
+        .assert()
+        .failure()
+        .stdout(tsv(&[
+            (path(root, "a.rs"), "1\t3\t10"),
+            (path(root, "b.txt"), "2\t3\t14"),
+            (path(root, "sub/c.rs"), "2\t6\t20"),
+        ]))
+        .stderr("-:2: invalid zero-length file name\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(unix)]
+#[test]
+fn recursive_unreadable_dir() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = make_tree()?;
+    let root = dir.path();
+    let sub = root.join("sub");
+    fs::set_permissions(&sub, fs::Permissions::from_mode(0o000))?;
+    // Permissions don't apply to root, so there is nothing to test
+    let readable = fs::read_dir(&sub).is_ok();
+    if !readable {
+        Command::cargo_bin(PRG)?
+            .args(["-r", "--format", "tsv"])
+            .arg(root)
+            .assert()
+            .failure()
+            .stdout(tsv(&[
+                (path(root, "a.rs"), "1\t3\t10"),
+                (path(root, "b.txt"), "2\t3\t14"),
+                ("total".to_string(), "3\t6\t24"),
+            ]))
+            .stderr(predicate::str::contains("sub"));
+    }
+    fs::set_permissions(&sub, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn directory_without_recursive() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", FOX])
+        .assert()
+        .failure()
+        .stdout("      1       9      48 tests/inputs/fox.txt\n")
+        .stderr(predicate::str::is_match("^tests/inputs: Is a directory .*\n$")?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_include_without_recursive() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--include", "*.rs", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--recursive"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn histogram_and_top_words() -> TestResult {
+    run(
+        &["--histogram", "lines", "--top-words", "3", FOX, ATLAMAL],
+        "tests/expected/fox_atlamal.histogram.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn top_words_stop_words() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let stop_words = dir.path().join("stop.txt");
+    fs::write(&stop_words, "THE\n\nfox\n")?;
+    Command::cargo_bin(PRG)?
+        .args(["-l", "--word-mode=unicode", "--top-words=2", "--stop-words"])
+        .arg(&stop_words)
+        .write_stdin("The fox and THE dog and the cat\n")
+        .assert()
+        .success()
+        .stdout("1\nstandard input: top words\n  2 and\n  1 cat\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn histogram_json() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-l", "--format=json", "--histogram=lines", "--bucket-size=20", FOX])
+        .assert()
+        .success()
+        .stdout(format!(
+            r#"{{"files":[{{"name":"{}","lines":1,"line_lengths":[{{"from":40,"to":59,"lines":1}}]}}]}}
+"#,
+            FOX
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_top_words_csv() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format=csv", "--top-words=3", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("can't be used with --format csv"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_missing_stop_words() -> TestResult {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["--top-words=2", "--stop-words", &bad, FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(format!("{}: ", bad)));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_top_words() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--top-words=0", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid value '0' for '--top-words <N>'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_bucket_size() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--histogram=lines", "--bucket-size=0", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid value '0' for '--bucket-size <CHARS>'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bucket_size_without_histogram() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--bucket-size=5", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("required arguments were not provided"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn progress_quiet_without_tty() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/atlamal.txt.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--progress", ATLAMAL])
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr("");
+    Ok(())
+}
+
+// --------------------------------------------------
+// Waits until the process catches SIGUSR1, as a signal sent any sooner
+// would end it
+#[cfg(target_os = "linux")]
+fn wait_for_sigusr1_handler(pid: u32) -> TestResult {
+    const SIGUSR1_BIT: u64 = 1 << (10 - 1);
+    for _ in 0..500 {
+        let status = fs::read_to_string(format!("/proc/{}/status", pid))?;
+        let caught = status
+            .lines()
+            .find_map(|line| line.strip_prefix("SigCgt:"))
+            .map(|mask| u64::from_str_radix(mask.trim(), 16))
+            .transpose()?;
+        if caught.is_some_and(|mask| mask & SIGUSR1_BIT != 0) {
+            return Ok(());
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    Err("SIGUSR1 handler was never installed".into())
+}
+
+#[cfg(target_os = "linux")]
+fn send_sigusr1(pid: u32) -> TestResult {
+    let status = std::process::Command::new("kill")
+        .args(["-USR1", &pid.to_string()])
+        .status()?;
+    assert!(status.success());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(target_os = "linux")]
+#[test]
+fn sigusr1_prints_partial_counts() -> TestResult {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        process::{Command as StdCommand, Stdio},
+        sync::mpsc,
+        thread,
+        time::Duration,
+    };
+
+    let mut child = StdCommand::new(assert_cmd::cargo::cargo_bin(PRG))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    let stderr = child.stderr.take().unwrap();
+    let (sender, lines) = mpsc::channel();
+    let reader = thread::spawn(move || {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            let _ = sender.send(line);
+        }
+    });
+    wait_for_sigusr1_handler(child.id())?;
+    stdin.write_all(b"one two\nthree\n")?;
+    stdin.flush()?;
+
+    // The input stays open, so the report must not wait for more of it.
+    // The first signals may come before the chunk has been counted.
+    let expected = "standard input: 2 lines, 3 words, 14 bytes";
+    let mut seen = vec![];
+    while !seen.iter().any(|line: &String| line.starts_with(expected)) {
+        assert!(seen.len() < 100, "stderr: {:?}", seen);
+        send_sigusr1(child.id())?;
+        if let Ok(line) = lines.recv_timeout(Duration::from_millis(100)) {
+            seen.push(line);
+        }
+    }
+    stdin.write_all(b"four\n")?;
+    drop(stdin);
+
+    let output = child.wait_with_output()?;
+    reader.join().unwrap();
+    seen.extend(lines.try_iter());
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "      3       4      19\n");
+    // stderr isn't a terminal, so there is no status line to clear
+    assert!(seen.iter().all(|line| !line.contains('\x1b')), "stderr: {:?}", seen);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(target_os = "linux")]
+#[test]
+fn sigusr1_while_reading_file_list() -> TestResult {
+    use std::{
+        io::Write,
+        process::{Command as StdCommand, Stdio},
+    };
+
+    let mut child = StdCommand::new(assert_cmd::cargo::cargo_bin(PRG))
+        .arg("--files0-from=-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    wait_for_sigusr1_handler(child.id())?;
+    send_sigusr1(child.id())?;
+    stdin.write_all(format!("{}\0", FOX).as_bytes())?;
+    drop(stdin);
+
+    let output = child.wait_with_output()?;
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        fs::read_to_string("tests/expected/fox.txt.out")?
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn diff_files() -> TestResult {
+    run(&["--diff", FOX, ATLAMAL], "tests/expected/fox_atlamal.diff.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn diff_json() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--diff", "-w", "--format", "json", EMPTY, FOX])
+        .assert()
+        .success()
+        .stdout(format!(
+            r#"[{{"old":"{}","new":"{}","changes":[{{"counter":"words","old":0,"new":9,"delta":9,"percent":null}}]}}]{}"#,
+            EMPTY, FOX, "\n"
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_diff_one_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--diff", FOX])
+        .assert()
+        .failure()
+        .stderr("--diff needs exactly two files, OLD and NEW\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+fn git(root: &Path, args: &[&str]) -> TestResult {
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(root)
+        .status()?;
+    assert!(status.success(), "git {:?} failed", args);
+    Ok(())
+}
+
+#[test]
+fn diff_git_revision() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    fs::create_dir(root.join("src"))?;
+    fs::write(root.join("src").join("a.txt"), "one two\nthree\n")?;
+    git(root, &["init", "-q"])?;
+    git(root, &["add", "."])?;
+    git(root, &["commit", "-q", "-m", "first"])?;
+    fs::write(root.join("src").join("a.txt"), "one two\nthree four\nfive\n")?;
+
+    Command::cargo_bin(PRG)?
+        .args(["--git", "HEAD", "--format", "csv", "src/a.txt"])
+        .current_dir(root)
+        .assert()
+        .success()
+        .stdout(concat!(
+            "old_name,new_name,counter,old,new,delta,percent\n",
+            "HEAD:src/a.txt,src/a.txt,lines,2,3,1,50.0\n",
+            "HEAD:src/a.txt,src/a.txt,words,3,5,2,66.7\n",
+            "HEAD:src/a.txt,src/a.txt,bytes,14,24,10,71.4\n",
+        ));
+
+    // Paths are relative to the current directory, not the repository root
+    Command::cargo_bin(PRG)?
+        .args(["--git", "HEAD", "-l", "a.txt"])
+        .current_dir(root.join("src"))
+        .assert()
+        .success()
+        .stdout("--- HEAD:a.txt\n+++ a.txt\n       old  new  delta  change\nlines    2    3     +1  +50.0%\n");
+
+    Command::cargo_bin(PRG)?
+        .args(["--git", "HEAD", "new.txt"])
+        .current_dir(root)
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with("git show HEAD:./new.txt: fatal:"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn cache_hits_and_maintenance() -> TestResult {
+    let dir = make_tree()?;
+    let root = dir.path();
+    let cache = path(root, "cache");
+    let a = path(root, "a.rs");
+    let b = path(root, "b.txt");
+    // Files modified within the last second are never cached
+    for name in [&a, &b] {
+        let file = fs::File::options().write(true).open(name)?;
+        file.set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(60))?;
+    }
+    let stdout = format!(" 1  3 10 {}\n 2  3 14 {}\n 3  6 24 total\n", a, b);
+
+    Command::cargo_bin(PRG)?
+        .args(["--cache", &cache, "-v", &a, &b])
+        .assert()
+        .success()
+        .stdout(stdout.clone())
+        .stderr("cache: 0 hits, 2 misses\n");
+    Command::cargo_bin(PRG)?
+        .args(["--cache", &cache, "-v", &a, &b])
+        .assert()
+        .success()
+        .stdout(stdout.clone())
+        .stderr(format!("{}: cache hit\n{}: cache hit\ncache: 2 hits, 0 misses\n", a, b));
+
+    // Other options are cached separately
+    Command::cargo_bin(PRG)?
+        .args(["--cache", &cache, "-v", "-l", &a])
+        .assert()
+        .success()
+        .stderr("cache: 0 hits, 1 misses\n");
+
+    Command::cargo_bin(PRG)?
+        .args(["--cache", &cache, "-v", "--cache-invalidate", &a])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(format!("{}: removed 2 cache entries\n", cache));
+    fs::write(&b, "changed\n")?;
+    Command::cargo_bin(PRG)?
+        .args(["--cache", &cache, "-v", "--cache-gc"])
+        .assert()
+        .success()
+        .stderr(format!("{}: removed 1 cache entries\n", cache));
+    Command::cargo_bin(PRG)?
+        .args(["--cache", &cache, "-v", "--cache-clear"])
+        .assert()
+        .success()
+        .stderr(format!("{}: removed 0 cache entries\n", cache));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_cache_dir_not_created() -> TestResult {
+    // A file is in the way, so the directory can't be created
+    let dir = format!("{}/cache", FOX);
+    Command::cargo_bin(PRG)?
+        .args(["--cache", &dir, FOX])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::starts_with(format!("{}: ", dir)));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_cache_command_without_cache() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--cache-gc"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--cache <DIR>"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn completions() -> TestResult {
+    for (shell, expected) in [
+        ("bash", "_wcr_v2()"),
+        ("zsh", "#compdef wcr_v2"),
+        ("fish", "complete -c wcr_v2"),
+        ("man", ".TH wcr_v2"),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(["completions", shell])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(expected))
+            .stdout(predicate::str::contains("files0"));
+    }
+    // The subcommand is for packagers, so it stays out of --help
+    Command::cargo_bin(PRG)?
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("completions").not());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn file_named_help() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("help"), "one two\n")?;
+    for args in [&["help"][..], &["-w", "help"], &["--", "help"]] {
+        Command::cargo_bin(PRG)?
+            .current_dir(dir.path())
+            .args(args)
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with(" help\n"))
+            .stdout(predicate::str::contains("Usage").not());
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn read_error_skips_file_and_continues() -> TestResult {
+    // Opening a directory works, reading from it fails. Like any input
+    // that isn't a regular file it widens the columns to 7.
+    let expected = concat!(
+        "      0       0       0 tests/inputs/empty.txt\n",
+        "      1       9      48 tests/inputs/fox.txt\n",
+        "      4      29     177 tests/inputs/atlamal.txt\n",
+        "      5      38     225 total\n",
+    );
+    Command::cargo_bin(PRG)?
+        .args([EMPTY, "tests/inputs", FOX, ATLAMAL])
+        .assert()
+        .failure()
+        .stdout(expected)
+        .stderr(predicate::str::is_match("^tests/inputs: Is a directory .*\n$")?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn read_error_json() -> TestResult {
+    let expected = format!(
+        r#"^\{{"files":\[\{{"name":"tests/inputs","error":"Is a directory .*"\}},\{{"name":"{}","lines":1\}},\{{"name":"{}","lines":4\}}\],"total":\{{"lines":5\}}\}}\n$"#,
+        FOX, ATLAMAL
+    );
+    Command::cargo_bin(PRG)?
+        .args(["--format", "json", "-l", "tests/inputs", FOX, ATLAMAL])
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exits_zero_when_everything_counted() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([EMPTY, FOX, ATLAMAL])
+        .assert()
+        .code(0);
+    Ok(())
+}
@@ -0,0 +1,8 @@
+// Greets the world.
+fn main() {
+    // Say hello!
+    println!("Hello, world!");
+}
+
+/* Block comments are not counted. */
+fn unused() {}